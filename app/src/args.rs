@@ -1,22 +1,46 @@
-use crate::music::{Note, NoteName};
+use crate::{
+    keyboard_layout::KeyboardLayout,
+    music::{note, Note, NoteName, Scale},
+};
+use std::path::PathBuf;
 
 pub struct Args {
     pub start_note: Note,
     pub volume_scale: f64,
     pub downsample: u32,
     pub render_scale: f64,
+    /// directory containing samples to use instead of the built-in drum kit
+    pub drum_kit: Option<PathBuf>,
+    /// play the keyboard through an arpeggiator rather than a synth per key
+    pub arp: bool,
+    /// lock the keyboard to the notes of this scale, starting from the start note
+    pub scale: Option<Scale>,
+    /// scala scale file to use instead of 12-tone equal temperament
+    pub scl: Option<PathBuf>,
+    /// scala keyboard mapping file
+    pub kbm: Option<PathBuf>,
+    pub keyboard_layout: KeyboardLayout,
+    /// TOML file describing the keyboard layout, used instead of `keyboard_layout`
+    pub keyboard_layout_file: Option<PathBuf>,
+    /// UDP port on which to listen for Open Sound Control messages
+    pub osc_port: Option<u16>,
+    /// preset file to load on startup
+    pub preset: Option<PathBuf>,
+    /// directory containing the presets shown in the preset browser
+    pub preset_dir: PathBuf,
 }
 
 impl Args {
     fn parser() -> impl meap::Parser<Item = Self> {
         meap::let_map! {
             let {
-                start_note_name = opt_opt_via::<NoteName, _, _>("NOTE", "start-note")
+                start_note = opt_opt_via::<Note, _, _>("NOTE", "start-note")
                     .name('n')
-                    .with_default(NoteName::C);
-                start_note_octave = opt_opt::<usize, _>("INT", "start-octave")
+                    .desc("note with octave in scientific pitch notation where middle C is C4 (e.g. C3, Db2, a-flat3) or MIDI note number")
+                    .with_default(note(NoteName::C, 3));
+                start_note_octave = opt_opt::<i32, _>("INT", "start-octave")
                     .name('o')
-                    .with_default(2);
+                    .desc("override the octave of the start note (scientific, so middle C is in octave 4)");
                 volume_scale = opt_opt::<f64, _>("FLOAT", "volume")
                     .name('v')
                     .with_default(1.0);
@@ -24,15 +48,48 @@ impl Args {
                     .with_default(1);
                 render_scale = opt_opt::<f64, _>("FLOAT", "render-scale")
                     .with_default(1.0);
+                drum_kit = opt_opt::<PathBuf, _>("PATH", "drum-kit")
+                    .desc("directory containing sn.wav, bd.wav and ch.wav");
+                arp = flag("arp").desc("arpeggiate held keys");
+                scale = opt_opt::<Scale, _>("SCALE", "scale")
+                    .desc("scale name (e.g. minor, blues) or comma-separated semitones");
+                scl = opt_opt::<PathBuf, _>("PATH", "scl")
+                    .desc("scala scale file (.scl) describing the tuning");
+                kbm = opt_opt::<PathBuf, _>("PATH", "kbm")
+                    .desc("scala keyboard mapping file (.kbm)");
+                keyboard_layout = opt_opt::<KeyboardLayout, _>("LAYOUT", "keyboard-layout")
+                    .name('k')
+                    .desc("qwerty, azerty, dvorak or tracker")
+                    .with_default_desc("qwerty", KeyboardLayout::qwerty());
+                keyboard_layout_file = opt_opt::<PathBuf, _>("PATH", "keyboard-layout-file")
+                    .desc("TOML file describing the keyboard layout");
+                osc_port = opt_opt::<u16, _>("PORT", "osc-port")
+                    .desc("listen for OSC messages on this UDP port");
+                preset = opt_opt::<PathBuf, _>("PATH", "preset")
+                    .name('p')
+                    .desc("preset file (.toml) to load on startup");
+                preset_dir = opt_opt::<PathBuf, _>("PATH", "preset-dir")
+                    .desc("directory where presets are saved")
+                    .with_default_desc("presets", PathBuf::from("presets"));
             } in {
                 Self {
                     start_note: Note {
-                        name: start_note_name,
-                        octave: start_note_octave,
+                        octave: start_note_octave.unwrap_or(start_note.octave),
+                        ..start_note
                     },
                     volume_scale,
                     downsample,
                     render_scale,
+                    drum_kit,
+                    arp,
+                    scale,
+                    scl,
+                    kbm,
+                    keyboard_layout,
+                    keyboard_layout_file,
+                    osc_port,
+                    preset,
+                    preset_dir,
                 }
             }
         }
@@ -0,0 +1,219 @@
+//! Assignment of computer keyboard keys to notes, drums and controls. Layouts can be loaded from
+//! TOML files such as:
+//!
+//! ```toml
+//! # tracker-style layout where the lower two rows play one octave and the upper two rows play
+//! # the next octave
+//! rows = [
+//!     { keys = "zsxdcvgbhnjm,l.;", offset = 0 },
+//!     { keys = "q2w3er5t6y7ui9o0p", offset = 12 },
+//! ]
+//! drums = ""
+//! octave_down = "-"
+//! octave_up = "="
+//! transpose_down = "["
+//! transpose_up = "]"
+//! velocity_modifier = " "
+//! modified_velocity_01 = 0.5
+//! ```
+//!
+//! Fields missing from the file are taken from the QWERTY layout.
+use serde::Deserialize;
+use std::{error, fmt, fs, io, path::Path, str::FromStr};
+
+#[derive(Debug)]
+pub enum KeyboardLayoutError {
+    UnknownPreset(String),
+    Io(io::Error),
+    Toml(toml::de::Error),
+}
+
+impl fmt::Display for KeyboardLayoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownPreset(name) => write!(f, "unknown keyboard layout: {}", name),
+            Self::Io(error) => write!(f, "io error: {}", error),
+            Self::Toml(error) => write!(f, "invalid keyboard layout: {}", error),
+        }
+    }
+}
+
+impl error::Error for KeyboardLayoutError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::UnknownPreset(_) => None,
+            Self::Io(error) => Some(error),
+            Self::Toml(error) => Some(error),
+        }
+    }
+}
+
+impl From<io::Error> for KeyboardLayoutError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<toml::de::Error> for KeyboardLayoutError {
+    fn from(error: toml::de::Error) -> Self {
+        Self::Toml(error)
+    }
+}
+
+/// A row of keys which play consecutive notes (or consecutive degrees of a scale)
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct KeyRow {
+    pub keys: String,
+    /// number of notes between the root and the first key of the row
+    #[serde(default)]
+    pub offset: i32,
+}
+
+impl KeyRow {
+    pub fn new(keys: impl Into<String>, offset: i32) -> Self {
+        Self {
+            keys: keys.into(),
+            offset,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct KeyboardLayout {
+    pub rows: Vec<KeyRow>,
+    /// keys which play each drum of the drum machine in order
+    pub drums: String,
+    pub octave_down: Option<char>,
+    pub octave_up: Option<char>,
+    pub transpose_down: Option<char>,
+    pub transpose_up: Option<char>,
+    /// while this key is held, notes are played with `modified_velocity_01`
+    pub velocity_modifier: Option<char>,
+    pub modified_velocity_01: f64,
+}
+
+impl Default for KeyboardLayout {
+    fn default() -> Self {
+        Self::qwerty()
+    }
+}
+
+impl KeyboardLayout {
+    // The qwerty, azerty and dvorak layouts play consecutive notes on alternating keys of the home
+    // row and the row above it, starting one note below the root. The bottom row plays the drums.
+
+    pub fn qwerty() -> Self {
+        Self {
+            rows: vec![KeyRow::new("asedrfgyhujiklp;", -1)],
+            drums: "zxcvbnm".to_string(),
+            octave_down: Some('-'),
+            octave_up: Some('='),
+            transpose_down: Some('['),
+            transpose_up: Some(']'),
+            velocity_modifier: Some(' '),
+            modified_velocity_01: 0.5,
+        }
+    }
+
+    pub fn azerty() -> Self {
+        Self {
+            rows: vec![KeyRow::new("qsedrfgyhujiklpm", -1)],
+            drums: "wxcvbn,".to_string(),
+            octave_down: Some(')'),
+            octave_up: Some('='),
+            transpose_down: Some('^'),
+            transpose_up: Some('$'),
+            ..Self::qwerty()
+        }
+    }
+
+    pub fn dvorak() -> Self {
+        Self {
+            rows: vec![KeyRow::new("ao.epuifdghctnls", -1)],
+            drums: ";qjkxbm".to_string(),
+            octave_down: Some('['),
+            octave_up: Some(']'),
+            transpose_down: Some('/'),
+            transpose_up: Some('='),
+            ..Self::qwerty()
+        }
+    }
+
+    /// Tracker-style QWERTY layout where the bottom two rows play one octave like a piano keyboard
+    /// and the top two rows play the octave above. The drums are on the right of the keyboard.
+    pub fn tracker() -> Self {
+        Self {
+            rows: vec![
+                KeyRow::new("zsxdcvgbhnjm", 0),
+                KeyRow::new("q2w3er5t6y7u", 12),
+            ],
+            drums: "iopkl;'".to_string(),
+            ..Self::qwerty()
+        }
+    }
+
+    pub fn from_toml(s: &str) -> Result<Self, KeyboardLayoutError> {
+        Ok(toml::from_str(s)?)
+    }
+
+    pub fn from_toml_file<P: AsRef<Path>>(path: P) -> Result<Self, KeyboardLayoutError> {
+        Self::from_toml(&fs::read_to_string(path)?)
+    }
+
+    /// Each key which plays a note along with the number of notes between the root and that key.
+    /// If a key appears more than once, the last occurrence takes precedence.
+    pub fn note_keys(&self) -> impl Iterator<Item = (char, i32)> + '_ {
+        self.rows.iter().flat_map(|row| {
+            row.keys
+                .chars()
+                .enumerate()
+                .map(move |(i, ch)| (ch, row.offset + i as i32))
+        })
+    }
+}
+
+impl FromStr for KeyboardLayout {
+    type Err = KeyboardLayoutError;
+
+    /// Parses the name of a preset layout: "qwerty", "azerty", "dvorak" or "tracker"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "qwerty" => Ok(Self::qwerty()),
+            "azerty" => Ok(Self::azerty()),
+            "dvorak" => Ok(Self::dvorak()),
+            "tracker" => Ok(Self::tracker()),
+            _ => Err(KeyboardLayoutError::UnknownPreset(s.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn qwerty_note_keys() {
+        // consecutive notes starting one note below the root
+        let expected = "asedrfgyhujiklp;"
+            .chars()
+            .enumerate()
+            .map(|(i, ch)| (ch, i as i32 - 1))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            KeyboardLayout::qwerty().note_keys().collect::<Vec<_>>(),
+            expected
+        );
+    }
+
+    #[test]
+    fn tracker_note_keys() {
+        let layout: KeyboardLayout = "tracker".parse().unwrap();
+        let note_keys = layout.note_keys().collect::<Vec<_>>();
+        assert_eq!(note_keys[0], ('z', 0));
+        assert_eq!(note_keys[11], ('m', 11));
+        assert_eq!(note_keys[12], ('q', 12));
+        assert_eq!(note_keys[23], ('u', 23));
+        assert!(!note_keys.iter().any(|(ch, _)| layout.drums.contains(*ch)));
+    }
+}
@@ -1,17 +1,85 @@
-use chargrid::{control_flow::*, core::*, prelude::*};
+use chargrid::{control_flow::*, core::*, prelude::*, text::StyledString};
 use rgb_int::Rgb24;
-use std::collections::{BTreeMap, HashMap};
+use std::{
+    collections::{BTreeMap, HashMap},
+    net::SocketAddr,
+    ops::RangeInclusive,
+};
 use synth_language::*;
 
+pub use synth_language::music;
+use synth_language::tuning::{KeyboardMap, Tuning};
+
 pub mod args;
-pub mod music;
+pub mod keyboard_layout;
+pub mod osc;
+pub mod preset;
 mod samples;
 mod signal_player;
 
 use args::Args;
-use signal_player::SignalPlayer;
+use keyboard_layout::KeyboardLayout;
+use osc::{OscArg, OscMessage, OscServer};
+use preset::PresetBrowser;
+use samples::DrumKit;
+use signal_player::{Meter, SignalPlayer};
+
+// Parameters are smoothed to avoid zipper noise when they are changed
+const PARAM_SMOOTHING: Smoothing = Smoothing::Exponential { seconds: 0.03 };
+
+/// Modulation shared by every voice of the key synth
+struct KeySynthModulation {
+    mod_matrix: ModMatrix,
+    mouse_x_01: Var<f64>,
+    mouse_y_01: Var<f64>,
+    mouse_x_01_signal: Sf64,
+    mouse_y_01_signal: Sf64,
+}
+
+impl KeySynthModulation {
+    const SOURCES: [&'static str; 6] = [
+        "envelope", "lfo", "random", "velocity", "mouse_x", "mouse_y",
+    ];
 
-fn make_key_synth(frequency_hz: Sf64, gate: Sbool, clock: Sbool) -> Sf64 {
+    fn new() -> Self {
+        let mod_matrix = ModMatrix::new(
+            "key_synth",
+            &Self::SOURCES,
+            &[
+                (
+                    "cutoff",
+                    ParamSpec::new(-5000.0, 5000.0, 0.0)
+                        .with_unit(ParamUnit::Hz)
+                        .with_smoothing(PARAM_SMOOTHING),
+                ),
+                (
+                    "resonance",
+                    ParamSpec::new(-10.0, 10.0, 0.0).with_smoothing(PARAM_SMOOTHING),
+                ),
+            ],
+        )
+        .with_route("envelope", "cutoff", 500.0)
+        .with_route("lfo", "cutoff", 1000.0)
+        .with_route("random", "cutoff", 500.0);
+        let (mouse_x_01_signal, mouse_x_01) = var_smoothed(0.0, PARAM_SMOOTHING);
+        let (mouse_y_01_signal, mouse_y_01) = var_smoothed(0.0, PARAM_SMOOTHING);
+        Self {
+            mod_matrix,
+            mouse_x_01,
+            mouse_y_01,
+            mouse_x_01_signal,
+            mouse_y_01_signal,
+        }
+    }
+}
+
+fn make_key_synth(
+    frequency_hz: Sf64,
+    gate: Sbool,
+    velocity_01: Sf64,
+    clock: Sbool,
+    modulation: &KeySynthModulation,
+) -> Sf64 {
     let noise = random_uniform();
     let lfo = lfo_01(
         const_(Waveform::Sine),
@@ -39,10 +107,22 @@ fn make_key_synth(frequency_hz: Sf64, gate: Sbool, clock: Sbool) -> Sf64 {
         .exp01(2.0),
         const_(5.0),
     );
+    // sources are in the same order as `KeySynthModulation::SOURCES`
+    let destinations = modulation.mod_matrix.create(
+        vec![
+            env,
+            lfo,
+            sah,
+            velocity_01,
+            modulation.mouse_x_01_signal.clone_ref(),
+            modulation.mouse_y_01_signal.clone_ref(),
+        ],
+        vec![const_(100.0), const_(10.0)],
+    );
     let filtered_osc = chebyshev_low_pass_filter(
         osc,
-        env.clone_ref() * 500.0 + 100.0 + lfo * 1000.0 + sah * 500.0,
-        const_(10.0),
+        destinations[0].clone_ref(),
+        destinations[1].clone_ref(),
     );
     amplify(
         filtered_osc,
@@ -50,155 +130,394 @@ fn make_key_synth(frequency_hz: Sf64, gate: Sbool, clock: Sbool) -> Sf64 {
     )
 }
 
-fn make_sequencer(sequencer_clock: Sbool, effect_clock: Sbool) -> Sf64 {
-    use music::{note, NoteName::*};
-    let octave_base = 2;
-    let note_sequence = vec![
-        (C, 0),
-        (C, 0),
-        (C, 0),
-        (C, 0),
-        (C, 0),
-        (C, 1),
-        (G, 0),
-        (C, 1),
-    ];
+fn make_pluck_synth(frequency_hz: Sf64, gate: Sbool) -> Sf64 {
+    plucked_string(frequency_hz, gate.trigger(), const_(1.5), const_(0.3))
+}
+
+fn make_sequencer(
+    tuning: &Tuning,
+    sequencer_clock: Sbool,
+    effect_clock: Sbool,
+    modulation: &KeySynthModulation,
+) -> Sf64 {
+    use music::{note, NoteName::*, Scale};
+    let root = note(C, 3);
+    let scale = Scale::major();
+    let degree_sequence = vec![0, 0, 0, 0, 0, 7, 4, 7];
     let note_period_seconds = 0.1;
-    let sequence = note_sequence
-        .iter()
-        .map(|&(note_name, octave_offset)| SynthSequencerStep {
-            frequency_hz: const_(note(note_name, octave_base + octave_offset).frequency()),
+    let sequence = degree_sequence
+        .into_iter()
+        .map(|degree| SynthSequencerStep {
+            // keys not mapped by the tuning are silent
+            frequency_hz: const_(
+                tuning
+                    .frequency(root.midi_number() + scale.degree_semitones(degree))
+                    .unwrap_or(0.0),
+            ),
             period_seconds: const_(note_period_seconds),
         })
         .collect();
     let SynthSequencerOutput { frequency_hz, gate } = synth_sequencer(sequence, sequencer_clock);
-    make_key_synth(frequency_hz, gate, effect_clock)
+    sum(vec![
+        make_key_synth(
+            frequency_hz.clone_ref(),
+            gate.clone_ref(),
+            const_(1.0),
+            effect_clock,
+            modulation,
+        ),
+        make_pluck_synth(frequency_hz, gate) * 0.5,
+    ])
 }
 
-fn make_drum_sequencer(sequencer_clock: Sbool) -> Sf64 {
-    const SNARE: u8 = 1 << 0;
-    const BASS: u8 = 1 << 1;
-    const SYMBOL: u8 = 1 << 2;
-    let sequence = vec![
-        SYMBOL | BASS,
-        SYMBOL,
-        SYMBOL | SNARE,
-        SYMBOL,
-        SYMBOL,
-        SYMBOL | BASS,
-        SYMBOL | SNARE | BASS,
-        SYMBOL,
-    ]
-    .into_iter()
-    .map(const_)
-    .collect();
+fn make_drum_sequencer(drum_kit: &DrumKit, sequencer_clock: Sbool) -> anyhow::Result<Sf64> {
+    let pattern = Pattern::parse("[bd ~ ~ ~ ~ bd bd ~], [~ ~ sn ~ ~ ~ sn ~], ch*8")?;
+    let sequence = pattern_trigger_sequence(&pattern, &["sn", "bd", "ch"])?;
     let [snare_trigger, bass_trigger, symbol_trigger, ..] =
         trigger_sequencer_8(sequence, sequencer_clock);
-    sum(vec![
-        sample_player(samples::sn01(), snare_trigger),
-        sample_player(samples::bd01(), bass_trigger),
-        sample_player(samples::ch01(), symbol_trigger),
-    ])
+    Ok(sum(vec![
+        sample_player(drum_kit.snare.clone(), snare_trigger),
+        sample_player(drum_kit.bass.clone(), bass_trigger),
+        sample_player(drum_kit.cymbal.clone(), symbol_trigger),
+    ]))
 }
 
+// Furthest the keyboard can be shifted from the start note in octaves
+const MAX_OCTAVE_SHIFT: i32 = 4;
+
 struct NoteKey {
-    frequency: f64,
+    /// number of notes (or scale degrees) between the root and this key
+    offset: i32,
+    frequency: Var<f64>,
+    velocity_01: Var<f64>,
     gate: Var<bool>,
 }
 
 impl NoteKey {
-    fn new(frequency: f64) -> Self {
+    fn new(offset: i32) -> Self {
         Self {
-            frequency,
+            offset,
+            frequency: Var::new(0.0),
+            velocity_01: Var::new(1.0),
             gate: Var::new(false),
         }
     }
 }
 
+/// Notes played by the computer keyboard, which can be shifted by octaves and transposed while
+/// the app is running
+struct Keyboard {
+    layout: KeyboardLayout,
+    tuning: Tuning,
+    root: music::Note,
+    scale: Option<music::Scale>,
+    notes: BTreeMap<char, NoteKey>,
+    /// keys of the tuning played by remote controllers, and the note keys whose voices they use
+    remote_notes: HashMap<i32, char>,
+    /// velocity of the most recently played note
+    velocity_01: Var<f64>,
+    keys_per_octave: i32,
+    octave_shift: i32,
+    transpose: i32,
+    velocity_modifier_held: bool,
+}
+
+impl Keyboard {
+    fn new(
+        layout: KeyboardLayout,
+        tuning: Tuning,
+        root: music::Note,
+        scale: Option<music::Scale>,
+    ) -> Self {
+        let notes = layout
+            .note_keys()
+            .map(|(ch, offset)| (ch, NoteKey::new(offset)))
+            .collect();
+        // number of keys spanning the period at which the tuning repeats
+        let keys_per_octave = match tuning.keyboard_map().mapping.len() {
+            0 => tuning.len(),
+            len => len,
+        } as i32;
+        Self {
+            layout,
+            tuning,
+            root,
+            scale,
+            notes,
+            remote_notes: HashMap::new(),
+            velocity_01: Var::new(1.0),
+            keys_per_octave,
+            octave_shift: 0,
+            transpose: 0,
+            velocity_modifier_held: false,
+        }
+    }
+
+    fn octave(&self) -> i32 {
+        self.root.octave + self.octave_shift
+    }
+
+    // Key of the tuning (numbered like MIDI notes) played by a note key
+    fn tuning_key(&self, note: &NoteKey) -> i32 {
+        let steps = self
+            .scale
+            .as_ref()
+            .map_or(note.offset, |scale| scale.degree_semitones(note.offset));
+        self.root.midi_number()
+            + steps
+            + self.transpose
+            + (self.octave_shift * self.keys_per_octave)
+    }
+
+    // Notes are retuned when they are played so that changing the octave or transposition doesn't
+    // change the pitch of held notes. Keys not mapped by the tuning are silent, in which case this
+    // returns false.
+    fn play(&self, note: &NoteKey, tuning_key: i32, velocity_01: f64) -> bool {
+        if let Some(frequency) = self.tuning.frequency(tuning_key) {
+            note.frequency.set(frequency);
+            note.velocity_01.set(velocity_01);
+            self.velocity_01.set(velocity_01);
+            note.gate.set(true);
+            true
+        } else {
+            false
+        }
+    }
+
+    fn key_down(&mut self, ch: char) {
+        let layout = &self.layout;
+        let max_transpose = self.keys_per_octave - 1;
+        if Some(ch) == layout.octave_down {
+            self.octave_shift = (self.octave_shift - 1).max(-MAX_OCTAVE_SHIFT);
+        } else if Some(ch) == layout.octave_up {
+            self.octave_shift = (self.octave_shift + 1).min(MAX_OCTAVE_SHIFT);
+        } else if Some(ch) == layout.transpose_down {
+            self.transpose = (self.transpose - 1).max(-max_transpose);
+        } else if Some(ch) == layout.transpose_up {
+            self.transpose = (self.transpose + 1).min(max_transpose);
+        } else if Some(ch) == layout.velocity_modifier {
+            self.velocity_modifier_held = true;
+        } else if let Some(note) = self.notes.get(&ch) {
+            let velocity_01 = if self.velocity_modifier_held {
+                layout.modified_velocity_01
+            } else {
+                1.0
+            };
+            self.play(note, self.tuning_key(note), velocity_01);
+        }
+    }
+
+    fn key_up(&mut self, ch: char) {
+        if Some(ch) == self.layout.velocity_modifier {
+            self.velocity_modifier_held = false;
+        } else if let Some(note) = self.notes.get(&ch) {
+            note.gate.set(false);
+        }
+    }
+
+    /// Plays a key of the tuning (numbered like MIDI notes) using the voice of a note key which
+    /// isn't currently playing. The note is dropped if every voice is playing.
+    fn note_on(&mut self, tuning_key: i32, velocity_01: f64) {
+        self.note_off(tuning_key);
+        let free_key = self
+            .notes
+            .iter()
+            .find(|(ch, note)| {
+                !note.gate.get() && !self.remote_notes.values().any(|remote| remote == *ch)
+            })
+            .map(|(&ch, _)| ch);
+        if let Some(ch) = free_key {
+            if self.play(&self.notes[&ch], tuning_key, velocity_01) {
+                self.remote_notes.insert(tuning_key, ch);
+            }
+        }
+    }
+
+    fn note_off(&mut self, tuning_key: i32) {
+        if let Some(ch) = self.remote_notes.remove(&tuning_key) {
+            self.notes[&ch].gate.set(false);
+        }
+    }
+}
+
 struct AppData {
     args: Args,
     mouse_coord: Option<Coord>,
-    mouse_x_var: Var<f64>,
-    mouse_y_var: Var<f64>,
+    params: ParamRegistry,
+    key_synth_modulation: KeySynthModulation,
+    preset_browser: PresetBrowser,
     signal_player: SignalPlayer,
     lit_coords: HashMap<Coord, u8>,
     signal: BufferedSignal<f32>,
     octave_range: u32,
+    keyboard: Keyboard,
     buttons: BTreeMap<char, BoolVar>,
+    osc_server: Option<OscServer>,
     frame_count: u64,
     recent_samples: Vec<f32>,
+    meter: Meter,
 }
 
-fn make_notes_even_temp(base_freq: f64, keys: &[char]) -> Vec<(char, NoteKey)> {
-    let mut mappings = Vec::new();
-    for (i, &ch) in keys.iter().enumerate() {
-        let freq = music::note_frequency_even_temperement(base_freq, i as f64 - 1.0);
-        mappings.push((ch, NoteKey::new(freq)));
-    }
-    mappings
-}
-
-fn sample_var(sample: Vec<f32>) -> (Sf64, TriggerVar) {
+fn sample_var(sample: Sample) -> (Sf64, TriggerVar) {
     let (trigger, var) = trigger_var();
     let player = sample_player(sample, trigger);
     (player, var)
 }
 
+fn drum_var<F: FnOnce(Sbool) -> Sf64>(make_drum: F) -> (Sf64, TriggerVar) {
+    let (trigger, var) = trigger_var();
+    (make_drum(trigger), var)
+}
+
+fn load_tuning(args: &Args) -> anyhow::Result<Tuning> {
+    let tuning = if let Some(path) = args.scl.as_ref() {
+        Tuning::from_scl_file(path)?
+    } else {
+        Tuning::default()
+    };
+    Ok(if let Some(path) = args.kbm.as_ref() {
+        tuning.with_keyboard_map(KeyboardMap::from_kbm_file(path)?)
+    } else {
+        tuning
+    })
+}
+
+fn load_keyboard_layout(args: &Args) -> anyhow::Result<KeyboardLayout> {
+    Ok(if let Some(path) = args.keyboard_layout_file.as_ref() {
+        KeyboardLayout::from_toml_file(path)?
+    } else {
+        args.keyboard_layout.clone()
+    })
+}
+
 impl AppData {
     fn new(args: Args) -> anyhow::Result<Self> {
-        samples::sn01();
+        let drum_kit = if let Some(path) = args.drum_kit.as_ref() {
+            DrumKit::load_dir(path)?
+        } else {
+            DrumKit::default_kit()?
+        };
         let signal_player = SignalPlayer::new(args.downsample)?;
-        let start_frequency = args.start_note.frequency();
-        let keyboard: BTreeMap<char, NoteKey> = vec![make_notes_even_temp(
-            start_frequency,
-            &[
-                'a', 's', 'e', 'd', 'r', 'f', 'g', 'y', 'h', 'u', 'j', 'i', 'k', 'l', 'p', ';',
-            ],
-        )
-        .into_iter()]
-        .into_iter()
-        .flatten()
-        .collect();
+        let osc_server = args.osc_port.map(OscServer::bind).transpose()?;
+        let tuning = load_tuning(&args)?;
+        let layout = load_keyboard_layout(&args)?;
+        let keyboard = Keyboard::new(layout, tuning.clone(), args.start_note, args.scale.clone());
         let effect_clock = clock(const_(6.0));
+        let mut params = ParamRegistry::new();
+        let key_synth_modulation = KeySynthModulation::new();
+        key_synth_modulation.mod_matrix.register(&mut params);
         let mut key_synths: Vec<Sf64> = Vec::new();
-        for note in keyboard.values() {
-            key_synths.push(make_key_synth(
-                const_(note.frequency),
-                note.gate.buffered_signal(),
-                effect_clock.clone_ref(),
+        if args.arp {
+            let SynthSequencerOutput { frequency_hz, gate } = arpeggiator(ArpeggiatorProps {
+                notes: keyboard
+                    .notes
+                    .values()
+                    .map(|note| ArpeggiatorNote {
+                        frequency_hz: note.frequency.buffered_signal(),
+                        gate: note.gate.buffered_signal(),
+                    })
+                    .collect(),
+                clock: effect_clock.clone_ref(),
+                order: ArpeggiatorOrder::UpDown,
+                octave_range: const_(2.0),
+                gate_length_01: const_(0.5),
+                latch: false,
+                seed: None,
+            });
+            let velocity_01 = keyboard.velocity_01.buffered_signal();
+            key_synths.push(amplify(
+                make_key_synth(
+                    frequency_hz,
+                    gate,
+                    velocity_01.clone_ref(),
+                    effect_clock.clone_ref(),
+                    &key_synth_modulation,
+                ),
+                velocity_01,
             ));
+        } else {
+            for note in keyboard.notes.values() {
+                key_synths.push(amplify(
+                    make_key_synth(
+                        note.frequency.buffered_signal(),
+                        note.gate.buffered_signal(),
+                        note.velocity_01.buffered_signal(),
+                        effect_clock.clone_ref(),
+                        &key_synth_modulation,
+                    ),
+                    note.velocity_01.buffered_signal(),
+                ));
+            }
         }
-        let drum_machine = maplit::btreemap! {
-            'z' => sample_var(samples::sn01()),
-            'x' => sample_var(samples::bd01()),
-            'c' => sample_var(samples::ch01()),
-        };
-        let (mouse_x_signal, mouse_x_var) = var(0.0_f64);
-        let (mouse_y_signal, mouse_y_var) = var(0.0_f64);
-        let sequencer_clock = clock(const_(3.0));
-        let sequencers = make_sequencer(sequencer_clock.clone_ref(), const_(false))
-            + (make_drum_sequencer(sequencer_clock) * 8.0);
+        // drums are assigned to the layout's drum keys in order
+        let drum_machine: BTreeMap<char, (Sf64, TriggerVar)> = keyboard
+            .layout
+            .drums
+            .chars()
+            .zip(vec![
+                sample_var(drum_kit.snare.clone()),
+                sample_var(drum_kit.bass.clone()),
+                sample_var(drum_kit.cymbal.clone()),
+                drum_var(|t| kick_drum(t, const_(50.0), const_(150.0), const_(0.5))),
+                drum_var(|t| snare_drum(t, const_(180.0), const_(0.2), const_(0.4))),
+                drum_var(|t| hi_hat(t, const_(205.3), const_(0.1), const_(10000.0))),
+                drum_var(|t| clap(t, const_(0.2), const_(1200.0))),
+            ])
+            .collect();
+        let cutoff_hz = params.register(
+            "cutoff",
+            ParamSpec::new(5000.0 * (-4.0_f64).exp(), 5000.0, 5000.0 * (-4.0_f64).exp())
+                .with_curve(ParamCurve::Exponential(4.0))
+                .with_unit(ParamUnit::Hz)
+                .with_smoothing(PARAM_SMOOTHING),
+        );
+        let resonance = params.register(
+            "resonance",
+            ParamSpec::new(0.0, 10.0, 0.0).with_smoothing(PARAM_SMOOTHING),
+        );
+        let mut preset_browser = PresetBrowser::new(&args.preset_dir);
+        if let Some(path) = args.preset.as_ref() {
+            preset_browser.load(path, &params)?;
+        }
+        let TransportOutput {
+            step: sequencer_clock,
+            ..
+        } = transport(const_(90.0), const_(4.0), const_(2.0));
+        let drum_sequencer = make_drum_sequencer(&drum_kit, sequencer_clock.clone_ref())? * 8.0;
+        let synth_sequencer = compressor(CompressorProps {
+            signal: make_sequencer(
+                &tuning,
+                sequencer_clock,
+                const_(false),
+                &key_synth_modulation,
+            ),
+            sidechain: drum_sequencer.clone_ref(),
+            threshold_db: const_(-24.0),
+            ratio: const_(4.0),
+            attack_seconds: const_(0.005),
+            release_seconds: const_(0.15),
+            knee_db: const_(6.0),
+            makeup_gain_db: const_(0.0),
+        });
+        let sequencers = synth_sequencer + drum_sequencer;
         let keyboard_synth = sum(key_synths);
         let drums = sum(drum_machine.values().map(|(s, _)| s.clone_ref()).collect());
         let manual_synth = sum(vec![keyboard_synth, drums]);
         let combined_synth = sum(vec![manual_synth, sequencers * 0.0]);
-        let filtered_synth = chebyshev_low_pass_filter(
-            combined_synth.clone_ref(),
-            butterworth_low_pass_filter(
-                mouse_x_signal.map(|x| 5000.0 * (4.0 * (x - 1.0)).exp()),
-                const_(5.0),
-            ),
-            mouse_y_signal * 10.0,
-        )
-        .map(|x| (x * 1.0).clamp(-2.0, 2.0));
-        let buttons = keyboard
+        let filtered_synth =
+            chebyshev_low_pass_filter(combined_synth.clone_ref(), cutoff_hz, resonance);
+        let limited_synth = limiter(filtered_synth, const_(0.0), 0.005, const_(0.1));
+        // soft clip the output to the range -2 to 2
+        let filtered_synth = waveshaper(
+            limited_synth,
+            WaveshaperCurve::Tanh,
+            const_(0.5),
+            const_(1.0),
+            4,
+        ) * 2.0;
+        let buttons = drum_machine
             .into_iter()
-            .map(|(key, NoteKey { frequency: _, gate })| (key, gate.bool_var()))
-            .chain(
-                drum_machine
-                    .into_iter()
-                    .map(|(ch, (_, var))| (ch, var.bool_var())),
-            )
+            .map(|(ch, (_, var))| (ch, var.bool_var()))
             .collect();
         Ok(Self {
             mouse_coord: None,
@@ -206,16 +525,132 @@ impl AppData {
             lit_coords: HashMap::new(),
             signal: filtered_synth.map(move |s| (s * args.volume_scale) as f32),
             octave_range: 24,
+            keyboard,
             buttons,
-            mouse_x_var,
-            mouse_y_var,
+            osc_server,
+            params,
+            key_synth_modulation,
+            preset_browser,
             frame_count: 0,
             recent_samples: Vec::new(),
+            meter: Default::default(),
             args,
         })
     }
 }
 
+const OSC_ADDRESS_PREFIX: &str = "/synth/";
+
+// Range of keys which can be played by "/synth/note" messages, which is the range of MIDI notes
+const OSC_NOTE_KEYS: RangeInclusive<i32> = 0..=127;
+
+// The key and velocity of the arguments of a "/synth/note" message, where the velocity defaults
+// to 1. Returns `None` if the key is out of range or either argument isn't a finite number.
+fn parse_osc_note(message: &OscMessage) -> Option<(i32, f64)> {
+    let values = message.args.iter().map(OscArg::f64).collect::<Vec<_>>();
+    let (key, velocity_01) = match values.as_slice() {
+        [Some(key)] => (*key, 1.0),
+        [Some(key), Some(velocity_01)] => (*key, *velocity_01),
+        _ => return None,
+    };
+    if !key.is_finite() || !velocity_01.is_finite() {
+        return None;
+    }
+    let key = key as i32;
+    if OSC_NOTE_KEYS.contains(&key) {
+        Some((key, velocity_01.clamp(0.0, 1.0)))
+    } else {
+        None
+    }
+}
+
+// Handles the messages which list, query and set parameters (see `AppData::handle_osc_message`),
+// where `name` is the address of the message without its prefix
+fn handle_osc_param_message(
+    params: &ParamRegistry,
+    name: &str,
+    message: &OscMessage,
+) -> Option<OscMessage> {
+    let values = message.args.iter().map(OscArg::f64).collect::<Vec<_>>();
+    if name == "params" && values.is_empty() {
+        return Some(OscMessage::new(
+            message.address.clone(),
+            params
+                .names()
+                .map(|name| OscArg::String(name.to_string()))
+                .collect(),
+        ));
+    }
+    let param = match params.param(name) {
+        Some(param) => param,
+        None => {
+            log::warn!("unhandled osc message: {:?}", message);
+            return None;
+        }
+    };
+    match values.as_slice() {
+        [] => Some(OscMessage::new(
+            message.address.clone(),
+            vec![OscArg::Float(param.get_01() as f32)],
+        )),
+        [Some(value_01)] if value_01.is_finite() => {
+            param.set_01(*value_01);
+            None
+        }
+        _ => {
+            log::warn!("invalid arguments for {}: {:?}", name, message.args);
+            None
+        }
+    }
+}
+
+impl AppData {
+    // Handles a message sent to one of the following addresses, returning a reply if the message
+    // is a query:
+    // - "/synth/params" replies with the name of each parameter
+    // - "/synth/note <key> [velocity]" plays a key of the tuning (numbered like MIDI notes) at a
+    //   velocity from 0 to 1, and a velocity of 0 releases the key
+    // - "/synth/<param> <value>" sets a parameter to a normalized value from 0 to 1
+    // - "/synth/<param>" replies with the normalized value of a parameter
+    fn handle_osc_message(&mut self, message: &OscMessage) -> Option<OscMessage> {
+        let name = match message.address.strip_prefix(OSC_ADDRESS_PREFIX) {
+            Some(name) => name,
+            None => {
+                log::warn!("unknown osc address: {}", message.address);
+                return None;
+            }
+        };
+        if name == "note" {
+            match parse_osc_note(message) {
+                Some((key, velocity_01)) => {
+                    if velocity_01 > 0.0 {
+                        self.keyboard.note_on(key, velocity_01);
+                    } else {
+                        self.keyboard.note_off(key);
+                    }
+                }
+                None => log::warn!("invalid arguments for note: {:?}", message.args),
+            }
+            return None;
+        }
+        handle_osc_param_message(&self.params, name, message)
+    }
+
+    fn handle_osc_messages(&mut self) {
+        let messages: Vec<(OscMessage, SocketAddr)> = match self.osc_server.as_mut() {
+            Some(osc_server) => osc_server.receive(),
+            None => return,
+        };
+        for (message, sender) in messages {
+            if let Some(reply) = self.handle_osc_message(&message) {
+                if let Some(osc_server) = self.osc_server.as_ref() {
+                    osc_server.send(&reply, sender);
+                }
+            }
+        }
+    }
+}
+
 struct GuiComponent;
 
 fn coord_to_rgba32(coord: Coord, size: Size) -> Rgba32 {
@@ -241,6 +676,75 @@ fn render_coord(coord: Coord, brightness: u8, size: Size, ctx: Ctx, fb: &mut Fra
     fb.set_cell_relative_to_ctx(ctx, coord, 0, cell);
 }
 
+fn meter_level_rgba32(level: f32) -> Rgba32 {
+    if level < 0.5 {
+        Rgba32::new_rgb(0, 255, 0)
+    } else if level < 0.9 {
+        Rgba32::new_rgb(255, 255, 0)
+    } else {
+        Rgba32::new_rgb(255, 0, 0)
+    }
+}
+
+// Draws a horizontal bar whose length is proportional to `level` where a level of 1 fills the
+// row. The final cell of the row is lit when `clipped` is true.
+fn render_meter_row(level: f32, clipped: bool, y: i32, size: Size, ctx: Ctx, fb: &mut FrameBuffer) {
+    let width = size.width() as i32;
+    let bar_width = ((level.min(1.0) * (width - 1) as f32) as i32).max(0);
+    for x in 0..bar_width {
+        let cell = RenderCell::default()
+            .with_character(' ')
+            .with_background(meter_level_rgba32(x as f32 / (width - 1) as f32));
+        fb.set_cell_relative_to_ctx(ctx, Coord::new(x, y), 1, cell);
+    }
+    if clipped {
+        let cell = RenderCell::default()
+            .with_character(' ')
+            .with_background(Rgba32::new_rgb(255, 0, 0));
+        fb.set_cell_relative_to_ctx(ctx, Coord::new(width - 1, y), 1, cell);
+    }
+}
+
+// Number of presets listed at once
+const PRESET_BROWSER_ROWS: usize = 5;
+
+// Lists the presets below the name of the loaded preset, marking the selected preset
+fn render_preset_browser(preset_browser: &PresetBrowser, ctx: Ctx, fb: &mut FrameBuffer) {
+    StyledString::plain_text(format!(
+        "preset {} (up/down: select, enter: load, F2: save)",
+        preset_browser
+            .loaded_name()
+            .unwrap_or_else(|| "none".to_string())
+    ))
+    .render(&(), ctx, fb);
+    // only a few presets are shown, scrolling to keep the selected preset in view
+    let names = preset_browser.names().collect::<Vec<_>>();
+    let selected_index = names
+        .iter()
+        .position(|(_, selected)| *selected)
+        .unwrap_or(0);
+    let first_index = selected_index
+        .saturating_sub(PRESET_BROWSER_ROWS / 2)
+        .min(names.len().saturating_sub(PRESET_BROWSER_ROWS));
+    for (i, (name, selected)) in names
+        .into_iter()
+        .skip(first_index)
+        .take(PRESET_BROWSER_ROWS)
+        .enumerate()
+    {
+        let (prefix, style) = if selected {
+            ("> ", Style::plain_text().with_bold(true))
+        } else {
+            ("  ", Style::plain_text())
+        };
+        StyledString {
+            string: format!("{}{}", prefix, name),
+            style,
+        }
+        .render(&(), ctx.add_y(i as i32 + 1), fb);
+    }
+}
+
 fn offset_to_freq_exp(offset: f64, base_freq: f64, octave_range: f64) -> f64 {
     base_freq * 2_f64.powf(offset / octave_range)
 }
@@ -273,6 +777,17 @@ impl Component for GuiComponent {
                 prev = coord;
             }
         }
+        let clipped = state.meter.clip_count > 0;
+        render_meter_row(state.meter.rms, clipped, 0, size, ctx, fb);
+        render_meter_row(state.meter.peak, clipped, 1, size, ctx, fb);
+        let keyboard = &state.keyboard;
+        StyledString::plain_text(format!(
+            "octave {} transpose {:+}",
+            keyboard.octave(),
+            keyboard.transpose
+        ))
+        .render(&(), ctx.add_y(2), fb);
+        render_preset_browser(&state.preset_browser, ctx.add_y(3), fb);
         for (coord, brightness) in state.lit_coords.iter() {
             render_coord(*coord, *brightness, size, ctx, fb);
         }
@@ -294,6 +809,15 @@ impl Component for GuiComponent {
                         state.mouse_coord = Some(coord);
                         state.lit_coords.insert(coord, 255);
                     }
+                    // the mouse only sets parameters when it moves so that it doesn't override
+                    // values set by other controllers
+                    let size = ctx.bounding_box.size();
+                    let x_01 = coord.x as f64 / size.width() as f64;
+                    let y_01 = coord.y as f64 / size.height() as f64;
+                    state.params.set_01("cutoff", x_01);
+                    state.params.set_01("resonance", y_01);
+                    state.key_synth_modulation.mouse_x_01.set(x_01);
+                    state.key_synth_modulation.mouse_y_01.set(y_01);
                 }
                 MouseInput::MousePress { .. } => {}
                 MouseInput::MouseRelease { .. } => {}
@@ -302,20 +826,45 @@ impl Component for GuiComponent {
         }
         if let Some(keyboard_input) = event.keyboard_input() {
             match keyboard_input {
+                KeyboardInput {
+                    key: Key::Up,
+                    event: KeyboardEvent::KeyDown,
+                } => state.preset_browser.select_prev(),
+                KeyboardInput {
+                    key: Key::Down,
+                    event: KeyboardEvent::KeyDown,
+                } => state.preset_browser.select_next(),
+                KeyboardInput {
+                    key: input::keys::RETURN,
+                    event: KeyboardEvent::KeyDown,
+                } => {
+                    if let Err(error) = state.preset_browser.load_selected(&state.params) {
+                        log::error!("failed to load preset: {}", error);
+                    }
+                }
+                KeyboardInput {
+                    key: Key::Function(2),
+                    event: KeyboardEvent::KeyDown,
+                } => match state.preset_browser.save_new(&state.params) {
+                    Ok(path) => log::info!("saved preset to {}", path.display()),
+                    Err(error) => log::error!("failed to save preset: {}", error),
+                },
                 KeyboardInput {
                     key: Key::Char(ref ch),
                     event: KeyboardEvent::KeyDown,
                 } => {
-                    if let Some(note) = state.buttons.get(ch) {
-                        note.set();
+                    state.keyboard.key_down(*ch);
+                    if let Some(button) = state.buttons.get(ch) {
+                        button.set();
                     }
                 }
                 KeyboardInput {
                     key: Key::Char(ref ch),
                     event: KeyboardEvent::KeyUp,
                 } => {
-                    if let Some(note) = state.buttons.get(ch) {
-                        note.clear();
+                    state.keyboard.key_up(*ch);
+                    if let Some(button) = state.buttons.get(ch) {
+                        button.clear();
                     }
                 }
                 _ => (),
@@ -328,13 +877,8 @@ impl Component for GuiComponent {
                     27.5_f64,
                     state.octave_range as f64,
                 );
-                state
-                    .mouse_x_var
-                    .set(mouse_coord.x as f64 / ctx.bounding_box.size().width() as f64);
-                state
-                    .mouse_y_var
-                    .set(mouse_coord.y as f64 / ctx.bounding_box.size().height() as f64);
             }
+            state.handle_osc_messages();
             state.lit_coords.retain(|_, brightness| {
                 *brightness = brightness.saturating_sub(20);
                 *brightness != 0
@@ -343,6 +887,7 @@ impl Component for GuiComponent {
             state
                 .signal_player
                 .swap_recent_samples(&mut state.recent_samples);
+            state.meter = state.signal_player.meter();
             state.frame_count += 1;
         }
     }
@@ -360,3 +905,111 @@ pub fn app(args: Args) -> anyhow::Result<App> {
         .ignore_output()
         .exit_on_close())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::{net::UdpSocket, thread, time::Duration};
+
+    // Polls the server until it receives a message, as its socket doesn't block
+    fn receive_one(server: &mut OscServer) -> (OscMessage, SocketAddr) {
+        for _ in 0..100 {
+            if let Some(received) = server.receive().pop() {
+                return received;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        panic!("no message received");
+    }
+
+    #[test]
+    fn osc_server_replies_with_params() {
+        let mut params = ParamRegistry::new();
+        params.register("cutoff", ParamSpec::new(0.0, 100.0, 50.0));
+        params.register("resonance", ParamSpec::new(0.0, 10.0, 0.0));
+        let mut server = OscServer::bind(0).unwrap();
+        let server_addr = SocketAddr::from(([127, 0, 0, 1], server.local_addr().unwrap().port()));
+        let controller = UdpSocket::bind("127.0.0.1:0").unwrap();
+        controller
+            .set_read_timeout(Some(Duration::from_secs(1)))
+            .unwrap();
+        let request = OscMessage::new("/synth/params", vec![]);
+        controller.send_to(&request.encode(), server_addr).unwrap();
+        let (message, sender) = receive_one(&mut server);
+        assert_eq!(message, request);
+        assert_eq!(sender, controller.local_addr().unwrap());
+        let name = message.address.strip_prefix(OSC_ADDRESS_PREFIX).unwrap();
+        let reply = handle_osc_param_message(&params, name, &message).unwrap();
+        server.send(&reply, sender);
+        let mut buffer = [0; 1024];
+        let len = controller.recv(&mut buffer).unwrap();
+        assert_eq!(
+            OscMessage::decode_packet(&buffer[0..len]).unwrap(),
+            vec![OscMessage::new(
+                "/synth/params",
+                vec![
+                    OscArg::String("cutoff".into()),
+                    OscArg::String("resonance".into()),
+                ],
+            )]
+        );
+    }
+
+    #[test]
+    fn osc_note_key_out_of_range() {
+        let mut server = OscServer::bind(0).unwrap();
+        let server_addr = SocketAddr::from(([127, 0, 0, 1], server.local_addr().unwrap().port()));
+        let controller = UdpSocket::bind("127.0.0.1:0").unwrap();
+        for args in [
+            vec![OscArg::Float(-3e9)],
+            vec![OscArg::Int(128)],
+            vec![OscArg::Double(f64::NAN)],
+            vec![OscArg::Int(60), OscArg::Float(f32::INFINITY)],
+        ] {
+            let request = OscMessage::new("/synth/note", args);
+            controller.send_to(&request.encode(), server_addr).unwrap();
+            let (message, _) = receive_one(&mut server);
+            assert_eq!(parse_osc_note(&message), None, "{:?}", message);
+        }
+    }
+
+    #[test]
+    fn osc_note() {
+        let note = |args| parse_osc_note(&OscMessage::new("/synth/note", args));
+        assert_eq!(note(vec![OscArg::Int(60)]), Some((60, 1.0)));
+        assert_eq!(
+            note(vec![OscArg::Float(0.0), OscArg::Float(0.5)]),
+            Some((0, 0.5))
+        );
+        assert_eq!(
+            note(vec![OscArg::Int(127), OscArg::Double(2.0)]),
+            Some((127, 1.0))
+        );
+        assert_eq!(note(vec![]), None);
+    }
+
+    #[test]
+    fn osc_set_param_ignores_non_finite_values() {
+        let mut params = ParamRegistry::new();
+        params.register("cutoff", ParamSpec::new(0.0, 100.0, 50.0));
+        for value in [f32::NAN, f32::INFINITY, f32::NEG_INFINITY] {
+            let set = OscMessage::new("/synth/cutoff", vec![OscArg::Float(value)]);
+            assert_eq!(handle_osc_param_message(&params, "cutoff", &set), None);
+            assert_eq!(params.get("cutoff"), Some(50.0));
+        }
+    }
+
+    #[test]
+    fn osc_set_and_query_param() {
+        let mut params = ParamRegistry::new();
+        params.register("cutoff", ParamSpec::new(0.0, 100.0, 50.0));
+        let set = OscMessage::new("/synth/cutoff", vec![OscArg::Float(0.25)]);
+        assert_eq!(handle_osc_param_message(&params, "cutoff", &set), None);
+        assert_eq!(params.get("cutoff"), Some(25.0));
+        let query = OscMessage::new("/synth/cutoff", vec![]);
+        assert_eq!(
+            handle_osc_param_message(&params, "cutoff", &query),
+            Some(OscMessage::new("/synth/cutoff", vec![OscArg::Float(0.25)]))
+        );
+    }
+}
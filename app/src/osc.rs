@@ -0,0 +1,415 @@
+//! Minimal Open Sound Control (OSC) 1.0 codec and a UDP server for receiving messages from
+//! remote controllers
+use std::{
+    error, fmt, io,
+    net::{Ipv4Addr, SocketAddr, UdpSocket},
+};
+
+// Largest packet which can be received
+const MAX_PACKET_SIZE: usize = 65536;
+
+const BUNDLE_TAG: &str = "#bundle";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum OscArg {
+    Int(i32),
+    Float(f32),
+    String(String),
+    Blob(Vec<u8>),
+    Long(i64),
+    Double(f64),
+    Bool(bool),
+    Nil,
+}
+
+impl OscArg {
+    /// The value of numeric and boolean arguments as a float
+    pub fn f64(&self) -> Option<f64> {
+        match self {
+            Self::Int(value) => Some(*value as f64),
+            Self::Float(value) => Some(*value as f64),
+            Self::Long(value) => Some(*value as f64),
+            Self::Double(value) => Some(*value),
+            Self::Bool(value) => Some(if *value { 1.0 } else { 0.0 }),
+            Self::String(_) | Self::Blob(_) | Self::Nil => None,
+        }
+    }
+
+    fn type_tag(&self) -> char {
+        match self {
+            Self::Int(_) => 'i',
+            Self::Float(_) => 'f',
+            Self::String(_) => 's',
+            Self::Blob(_) => 'b',
+            Self::Long(_) => 'h',
+            Self::Double(_) => 'd',
+            Self::Bool(true) => 'T',
+            Self::Bool(false) => 'F',
+            Self::Nil => 'N',
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OscDecodeError {
+    /// byte offset into the packet where the error was found
+    pub position: usize,
+    pub message: String,
+}
+
+impl fmt::Display for OscDecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (at byte {})", self.message, self.position)
+    }
+}
+
+impl error::Error for OscDecodeError {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OscMessage {
+    pub address: String,
+    pub args: Vec<OscArg>,
+}
+
+// Strings and blobs are padded with zeros to a multiple of 4 bytes
+fn pad(bytes: &mut Vec<u8>) {
+    while !bytes.len().is_multiple_of(4) {
+        bytes.push(0);
+    }
+}
+
+fn encode_string(s: &str, bytes: &mut Vec<u8>) {
+    bytes.extend_from_slice(s.as_bytes());
+    bytes.push(0);
+    pad(bytes);
+}
+
+impl OscMessage {
+    pub fn new(address: impl Into<String>, args: Vec<OscArg>) -> Self {
+        Self {
+            address: address.into(),
+            args,
+        }
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        encode_string(&self.address, &mut bytes);
+        let type_tags = std::iter::once(',')
+            .chain(self.args.iter().map(OscArg::type_tag))
+            .collect::<String>();
+        encode_string(&type_tags, &mut bytes);
+        for arg in &self.args {
+            match arg {
+                OscArg::Int(value) => bytes.extend_from_slice(&value.to_be_bytes()),
+                OscArg::Float(value) => bytes.extend_from_slice(&value.to_be_bytes()),
+                OscArg::String(value) => encode_string(value, &mut bytes),
+                OscArg::Blob(value) => {
+                    bytes.extend_from_slice(&(value.len() as i32).to_be_bytes());
+                    bytes.extend_from_slice(value);
+                    pad(&mut bytes);
+                }
+                OscArg::Long(value) => bytes.extend_from_slice(&value.to_be_bytes()),
+                OscArg::Double(value) => bytes.extend_from_slice(&value.to_be_bytes()),
+                OscArg::Bool(_) | OscArg::Nil => (),
+            }
+        }
+        bytes
+    }
+
+    /// Decodes a packet containing either a message or a bundle. The messages of bundles
+    /// (including nested bundles) are returned in order, ignoring their time tags.
+    pub fn decode_packet(bytes: &[u8]) -> Result<Vec<Self>, OscDecodeError> {
+        let mut messages = Vec::new();
+        Reader::new(bytes).packet(&mut messages)?;
+        Ok(messages)
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    index: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, index: 0 }
+    }
+
+    fn error<T>(&self, message: impl Into<String>) -> Result<T, OscDecodeError> {
+        Err(OscDecodeError {
+            position: self.index,
+            message: message.into(),
+        })
+    }
+
+    fn is_empty(&self) -> bool {
+        self.index >= self.bytes.len()
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], OscDecodeError> {
+        if self.index + len > self.bytes.len() {
+            return self.error("unexpected end of packet");
+        }
+        let bytes = &self.bytes[self.index..(self.index + len)];
+        self.index += len;
+        Ok(bytes)
+    }
+
+    fn take_array<const N: usize>(&mut self) -> Result<[u8; N], OscDecodeError> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.take(N)?);
+        Ok(array)
+    }
+
+    fn skip_padding(&mut self) {
+        self.index = self.index.next_multiple_of(4).min(self.bytes.len());
+    }
+
+    fn string(&mut self) -> Result<String, OscDecodeError> {
+        let len = match self.bytes[self.index..].iter().position(|&b| b == 0) {
+            Some(len) => len,
+            None => return self.error("unterminated string"),
+        };
+        let start = self.index;
+        let string = match std::str::from_utf8(self.take(len)?) {
+            Ok(string) => string.to_string(),
+            Err(_) => {
+                self.index = start;
+                return self.error("string is not valid utf-8");
+            }
+        };
+        self.index += 1;
+        self.skip_padding();
+        Ok(string)
+    }
+
+    fn i32(&mut self) -> Result<i32, OscDecodeError> {
+        Ok(i32::from_be_bytes(self.take_array()?))
+    }
+
+    fn packet(&mut self, messages: &mut Vec<OscMessage>) -> Result<(), OscDecodeError> {
+        if !self.bytes.len().is_multiple_of(4) {
+            return self.error("packet size is not a multiple of 4");
+        }
+        if self.bytes.get(self.index) == Some(&b'#') {
+            self.bundle(messages)
+        } else {
+            messages.push(self.message()?);
+            Ok(())
+        }
+    }
+
+    fn bundle(&mut self, messages: &mut Vec<OscMessage>) -> Result<(), OscDecodeError> {
+        if self.string()? != BUNDLE_TAG {
+            return self.error("expected bundle");
+        }
+        // messages are handled as soon as they arrive so the time tag is ignored
+        let _time_tag = self.take(8)?;
+        while !self.is_empty() {
+            let size = self.i32()?;
+            if size < 0 {
+                return self.error("negative bundle element size");
+            }
+            let element = self.take(size as usize)?;
+            let start = self.index - element.len();
+            Reader::new(element)
+                .packet(messages)
+                .map_err(|error| OscDecodeError {
+                    position: start + error.position,
+                    ..error
+                })?;
+        }
+        Ok(())
+    }
+
+    fn message(&mut self) -> Result<OscMessage, OscDecodeError> {
+        let address = self.string()?;
+        if !address.starts_with('/') {
+            return self.error("address must start with '/'");
+        }
+        // type tags are optional in older implementations
+        let type_tags = if self.is_empty() {
+            String::new()
+        } else {
+            self.string()?
+        };
+        let mut args = Vec::new();
+        for type_tag in type_tags.chars().skip_while(|&ch| ch == ',') {
+            let arg = match type_tag {
+                'i' => OscArg::Int(self.i32()?),
+                'f' => OscArg::Float(f32::from_be_bytes(self.take_array()?)),
+                's' | 'S' => OscArg::String(self.string()?),
+                'b' => {
+                    let len = self.i32()?;
+                    if len < 0 {
+                        return self.error("negative blob size");
+                    }
+                    let blob = self.take(len as usize)?.to_vec();
+                    self.skip_padding();
+                    OscArg::Blob(blob)
+                }
+                'h' => OscArg::Long(i64::from_be_bytes(self.take_array()?)),
+                'd' => OscArg::Double(f64::from_be_bytes(self.take_array()?)),
+                'T' => OscArg::Bool(true),
+                'F' => OscArg::Bool(false),
+                'N' | 'I' => OscArg::Nil,
+                other => return self.error(format!("unsupported type tag '{}'", other)),
+            };
+            args.push(arg);
+        }
+        Ok(OscMessage { address, args })
+    }
+}
+
+/// Receives OSC messages over UDP without blocking, so it can be polled from the UI thread
+pub struct OscServer {
+    socket: UdpSocket,
+    buffer: Vec<u8>,
+}
+
+impl OscServer {
+    /// Listens on the given port on all interfaces
+    pub fn bind(port: u16) -> io::Result<Self> {
+        let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, port))?;
+        socket.set_nonblocking(true)?;
+        log::info!("listening for osc messages on {}", socket.local_addr()?);
+        Ok(Self {
+            socket,
+            buffer: vec![0; MAX_PACKET_SIZE],
+        })
+    }
+
+    /// All messages received since the last call along with the address of their senders.
+    /// Packets which can't be decoded are logged and skipped.
+    pub fn receive(&mut self) -> Vec<(OscMessage, SocketAddr)> {
+        let mut messages = Vec::new();
+        loop {
+            match self.socket.recv_from(&mut self.buffer) {
+                Ok((len, sender)) => match OscMessage::decode_packet(&self.buffer[0..len]) {
+                    Ok(decoded) => messages.extend(decoded.into_iter().map(|m| (m, sender))),
+                    Err(error) => log::warn!("invalid osc packet from {}: {}", sender, error),
+                },
+                Err(error) if error.kind() == io::ErrorKind::WouldBlock => break,
+                Err(error) => {
+                    log::error!("failed to receive osc packet: {}", error);
+                    break;
+                }
+            }
+        }
+        messages
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+
+    pub fn send(&self, message: &OscMessage, address: SocketAddr) {
+        if let Err(error) = self.socket.send_to(&message.encode(), address) {
+            log::error!("failed to send osc message to {}: {}", address, error);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn round_trip(message: &OscMessage) -> Vec<OscMessage> {
+        OscMessage::decode_packet(&message.encode()).unwrap()
+    }
+
+    // Bundle with a zero time tag containing the given packets
+    fn bundle(elements: &[Vec<u8>]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        encode_string(BUNDLE_TAG, &mut bytes);
+        bytes.extend_from_slice(&[0; 8]);
+        for element in elements {
+            bytes.extend_from_slice(&(element.len() as i32).to_be_bytes());
+            bytes.extend_from_slice(element);
+        }
+        bytes
+    }
+
+    fn decode_error_position(bytes: &[u8]) -> usize {
+        OscMessage::decode_packet(bytes).unwrap_err().position
+    }
+
+    #[test]
+    fn encode() {
+        let message = OscMessage::new("/a", vec![OscArg::Int(1), OscArg::String("bc".into())]);
+        assert_eq!(message.encode(), b"/a\0\0,is\0\0\0\0\x01bc\0\0".to_vec());
+    }
+
+    #[test]
+    fn round_trip_args() {
+        let message = OscMessage::new(
+            "/synth/cutoff",
+            vec![
+                OscArg::Int(-7),
+                OscArg::Float(0.25),
+                OscArg::String("".into()),
+                OscArg::String("abc".into()),
+                OscArg::String("abcd".into()),
+            ],
+        );
+        assert_eq!(round_trip(&message), vec![message]);
+    }
+
+    #[test]
+    fn round_trip_other_args() {
+        let message = OscMessage::new(
+            "/x",
+            vec![
+                OscArg::Blob(vec![1, 2, 3, 4, 5]),
+                OscArg::Long(1 << 40),
+                OscArg::Double(0.1),
+                OscArg::Bool(true),
+                OscArg::Bool(false),
+                OscArg::Nil,
+                OscArg::Int(3),
+            ],
+        );
+        assert_eq!(round_trip(&message), vec![message]);
+    }
+
+    #[test]
+    fn nested_bundles() {
+        let a = OscMessage::new("/a", vec![OscArg::Int(1)]);
+        let b = OscMessage::new("/b", vec![OscArg::Float(2.0)]);
+        let c = OscMessage::new("/c", vec![]);
+        let packet = bundle(&[a.encode(), bundle(&[b.encode(), bundle(&[])]), c.encode()]);
+        assert_eq!(OscMessage::decode_packet(&packet).unwrap(), vec![a, b, c]);
+    }
+
+    #[test]
+    fn truncated_message() {
+        let bytes = OscMessage::new("/a", vec![OscArg::Int(1), OscArg::Int(2)]).encode();
+        // the second int starts after the address, type tags and first int
+        assert_eq!(decode_error_position(&bytes[0..(bytes.len() - 4)]), 12);
+        assert_eq!(decode_error_position(b"/abc"), 0);
+    }
+
+    #[test]
+    fn truncated_bundle() {
+        let mut packet = bundle(&[OscMessage::new("/a", vec![]).encode()]);
+        packet.truncate(packet.len() - 4);
+        // the element's size is after the bundle tag and time tag
+        assert_eq!(decode_error_position(&packet), 20);
+    }
+
+    #[test]
+    fn misaligned_packet() {
+        assert_eq!(decode_error_position(b"/a\0,i\0\0\0\0\0\x01"), 0);
+        // the bundle as a whole is misaligned by an element whose size isn't a multiple of 4
+        assert_eq!(decode_error_position(&bundle(&[b"/a\0".to_vec()])), 0);
+        // elements whose sizes aren't multiples of 4 within a bundle whose size is
+        let packet = bundle(&[b"/a\0".to_vec(), b"/".to_vec()]);
+        assert_eq!(decode_error_position(&packet), 20);
+    }
+
+    #[test]
+    fn address_without_slash() {
+        assert_eq!(decode_error_position(b"a\0\0\0,\0\0\0"), 4);
+    }
+}
@@ -0,0 +1,307 @@
+//! Snapshots of parameter values saved as TOML files such as:
+//!
+//! ```toml
+//! [params]
+//! cutoff = 400.0
+//! resonance = 2.5
+//! ```
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    error, fmt, fs, io,
+    path::{Path, PathBuf},
+};
+use synth_language::ParamRegistry;
+
+const PRESET_EXTENSION: &str = "toml";
+
+#[derive(Debug)]
+pub enum PresetError {
+    Io(io::Error),
+    Parse(toml::de::Error),
+    Serialize(toml::ser::Error),
+}
+
+impl fmt::Display for PresetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "io error: {}", error),
+            Self::Parse(error) => write!(f, "invalid preset: {}", error),
+            Self::Serialize(error) => write!(f, "failed to serialize preset: {}", error),
+        }
+    }
+}
+
+impl error::Error for PresetError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::Io(error) => Some(error),
+            Self::Parse(error) => Some(error),
+            Self::Serialize(error) => Some(error),
+        }
+    }
+}
+
+impl From<io::Error> for PresetError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<toml::de::Error> for PresetError {
+    fn from(error: toml::de::Error) -> Self {
+        Self::Parse(error)
+    }
+}
+
+impl From<toml::ser::Error> for PresetError {
+    fn from(error: toml::ser::Error) -> Self {
+        Self::Serialize(error)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Preset {
+    /// values are kept as arbitrary TOML values so that a single malformed value doesn't prevent
+    /// the rest of the preset from loading
+    #[serde(default)]
+    params: BTreeMap<String, toml::Value>,
+}
+
+impl Preset {
+    /// Snapshot of the current value of every parameter
+    pub fn capture(params: &ParamRegistry) -> Self {
+        Self {
+            params: params
+                .iter()
+                .map(|param| (param.name().to_string(), toml::Value::Float(param.get())))
+                .collect(),
+        }
+    }
+
+    /// Sets each parameter to its value in the preset. Parameters missing from the preset are
+    /// reset to their default values, and values in the preset which don't correspond to a
+    /// parameter or which aren't finite numbers are ignored.
+    pub fn apply(&self, params: &ParamRegistry) {
+        params.reset_all();
+        for (name, value) in &self.params {
+            let value = match value {
+                toml::Value::Float(value) if value.is_finite() => *value,
+                toml::Value::Integer(value) => *value as f64,
+                other => {
+                    log::warn!(
+                        "ignoring non-numeric or non-finite value for {} in preset: {}",
+                        name,
+                        other
+                    );
+                    continue;
+                }
+            };
+            if !params.set(name, value) {
+                log::warn!("ignoring unknown parameter in preset: {}", name);
+            }
+        }
+    }
+
+    pub fn from_toml(s: &str) -> Result<Self, PresetError> {
+        Ok(toml::from_str(s)?)
+    }
+
+    pub fn to_toml(&self) -> Result<String, PresetError> {
+        Ok(toml::to_string(self)?)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, PresetError> {
+        Self::from_toml(&fs::read_to_string(path)?)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), PresetError> {
+        Ok(fs::write(path, self.to_toml()?)?)
+    }
+}
+
+/// The presets in a directory, one of which is selected
+pub struct PresetBrowser {
+    dir: PathBuf,
+    paths: Vec<PathBuf>,
+    selected: usize,
+    loaded: Option<PathBuf>,
+}
+
+fn preset_name(path: &Path) -> String {
+    path.file_stem().map_or_else(
+        || path.display().to_string(),
+        |s| s.to_string_lossy().into(),
+    )
+}
+
+impl PresetBrowser {
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Self {
+        let mut browser = Self {
+            dir: dir.into(),
+            paths: Vec::new(),
+            selected: 0,
+            loaded: None,
+        };
+        browser.refresh();
+        browser
+    }
+
+    /// Finds the presets in the directory again. The directory not existing is treated as it
+    /// containing no presets.
+    pub fn refresh(&mut self) {
+        self.paths = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.extension().is_some_and(|ext| ext == PRESET_EXTENSION))
+                .collect(),
+            Err(error) => {
+                if error.kind() != io::ErrorKind::NotFound {
+                    log::warn!("failed to read preset directory: {}", error);
+                }
+                Vec::new()
+            }
+        };
+        self.paths.sort();
+        self.selected = self.selected.min(self.paths.len().saturating_sub(1));
+    }
+
+    pub fn select_next(&mut self) {
+        if !self.paths.is_empty() {
+            self.selected = (self.selected + 1) % self.paths.len();
+        }
+    }
+
+    pub fn select_prev(&mut self) {
+        if !self.paths.is_empty() {
+            self.selected = (self.selected + self.paths.len() - 1) % self.paths.len();
+        }
+    }
+
+    /// Loads a preset (which needn't be in the directory), selecting it if it is in the directory
+    pub fn load<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        params: &ParamRegistry,
+    ) -> Result<(), PresetError> {
+        let path = path.as_ref();
+        Preset::load(path)?.apply(params);
+        if let Some(index) = self.paths.iter().position(|p| p == path) {
+            self.selected = index;
+        }
+        self.loaded = Some(path.to_path_buf());
+        Ok(())
+    }
+
+    pub fn load_selected(&mut self, params: &ParamRegistry) -> Result<(), PresetError> {
+        match self.paths.get(self.selected).cloned() {
+            Some(path) => self.load(path, params),
+            None => Ok(()),
+        }
+    }
+
+    /// Saves the current parameter values as a new preset named "preset-<n>", returning its path
+    pub fn save_new(&mut self, params: &ParamRegistry) -> Result<PathBuf, PresetError> {
+        fs::create_dir_all(&self.dir)?;
+        let path = (1..)
+            .map(|n| self.dir.join(format!("preset-{}.{}", n, PRESET_EXTENSION)))
+            .find(|path| !path.exists())
+            .unwrap();
+        Preset::capture(params).save(&path)?;
+        self.refresh();
+        if let Some(index) = self.paths.iter().position(|p| *p == path) {
+            self.selected = index;
+        }
+        self.loaded = Some(path.clone());
+        Ok(path)
+    }
+
+    /// The name of each preset along with whether it is selected
+    pub fn names(&self) -> impl Iterator<Item = (String, bool)> + '_ {
+        self.paths
+            .iter()
+            .enumerate()
+            .map(|(i, path)| (preset_name(path), i == self.selected))
+    }
+
+    pub fn loaded_name(&self) -> Option<String> {
+        self.loaded.as_deref().map(preset_name)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use synth_language::ParamSpec;
+
+    fn params() -> ParamRegistry {
+        let mut params = ParamRegistry::new();
+        params.register("cutoff", ParamSpec::new(0.0, 1000.0, 500.0));
+        params.register("resonance", ParamSpec::new(0.0, 10.0, 0.0));
+        params.register(
+            "key_synth/envelope/cutoff",
+            ParamSpec::new(-10.0, 10.0, 0.0),
+        );
+        params
+    }
+
+    #[test]
+    fn apply_tolerates_bad_keys() {
+        let params = params();
+        params.set("resonance", 5.0);
+        let preset = Preset::from_toml(
+            r#"
+            [params]
+            cutoff = 250
+            unknown = 1.0
+            "key_synth/envelope/cutoff" = "loud"
+            "#,
+        )
+        .unwrap();
+        preset.apply(&params);
+        assert_eq!(params.get("cutoff"), Some(250.0));
+        // missing from the preset so reset to its default
+        assert_eq!(params.get("resonance"), Some(0.0));
+        // non-numeric so left at its default
+        assert_eq!(params.get("key_synth/envelope/cutoff"), Some(0.0));
+    }
+
+    #[test]
+    fn apply_ignores_non_finite_values() {
+        let params = params();
+        let preset = Preset::from_toml(
+            r#"
+            [params]
+            cutoff = nan
+            resonance = inf
+            "key_synth/envelope/cutoff" = -inf
+            "#,
+        )
+        .unwrap();
+        preset.apply(&params);
+        assert_eq!(params.get("cutoff"), Some(500.0));
+        assert_eq!(params.get("resonance"), Some(0.0));
+        assert_eq!(params.get("key_synth/envelope/cutoff"), Some(0.0));
+    }
+
+    #[test]
+    fn empty_preset() {
+        assert_eq!(Preset::from_toml("").unwrap(), Preset::default());
+        assert!(Preset::from_toml("params = 1").is_err());
+    }
+
+    #[test]
+    fn round_trip() {
+        let params = params();
+        params.set("cutoff", 123.5);
+        params.set("key_synth/envelope/cutoff", -2.0);
+        let toml = Preset::capture(&params).to_toml().unwrap();
+        assert!(toml.contains("\"key_synth/envelope/cutoff\" = -2.0"));
+        let preset = Preset::from_toml(&toml).unwrap();
+        assert_eq!(preset, Preset::capture(&params));
+        params.reset_all();
+        preset.apply(&params);
+        assert_eq!(params.get("cutoff"), Some(123.5));
+        assert_eq!(params.get("key_synth/envelope/cutoff"), Some(-2.0));
+    }
+}
@@ -1,32 +1,30 @@
-use hound::WavReader;
-use std::io::BufReader;
+use std::path::Path;
+use synth_language::{load_wav_dir, Sample, SampleLoadError};
 
-fn load_wav(buffer: &[u8]) -> Vec<f32> {
-    let mut reader = WavReader::new(BufReader::new(buffer)).unwrap();
-    let spec = reader.spec();
-    let max_value = (1 << (spec.bits_per_sample - 1)) as i64;
-    let data_int = reader
-        .samples::<i32>()
-        .map(|x| x.unwrap())
-        .collect::<Vec<_>>();
-    let data_f32 = data_int
-        .chunks(spec.channels as usize)
-        .map(|chunk| {
-            let channel_mean = chunk.iter().map(|&x| x as i64).sum::<i64>() / chunk.len() as i64;
-            (channel_mean as f64 / max_value as f64) as f32
-        })
-        .collect::<Vec<_>>();
-    data_f32
-}
-
-pub fn sn01() -> Vec<f32> {
-    load_wav(include_bytes!("./sn01.wav"))
+pub struct DrumKit {
+    pub snare: Sample,
+    pub bass: Sample,
+    pub cymbal: Sample,
 }
 
-pub fn bd01() -> Vec<f32> {
-    load_wav(include_bytes!("./bd01.wav"))
-}
+impl DrumKit {
+    pub fn default_kit() -> Result<Self, SampleLoadError> {
+        Ok(Self {
+            snare: Sample::from_wav_bytes(include_bytes!("./sn01.wav"))?,
+            bass: Sample::from_wav_bytes(include_bytes!("./bd01.wav"))?,
+            cymbal: Sample::from_wav_bytes(include_bytes!("./ch01.wav"))?,
+        })
+    }
 
-pub fn ch01() -> Vec<f32> {
-    load_wav(include_bytes!("./ch01.wav"))
+    /// Loads the files "sn.wav", "bd.wav" and "ch.wav" from a directory. Samples missing from the
+    /// directory are taken from the default kit.
+    pub fn load_dir<P: AsRef<Path>>(path: P) -> Result<Self, SampleLoadError> {
+        let mut samples = load_wav_dir(path)?;
+        let default_kit = Self::default_kit()?;
+        Ok(Self {
+            snare: samples.remove("sn").unwrap_or(default_kit.snare),
+            bass: samples.remove("bd").unwrap_or(default_kit.bass),
+            cymbal: samples.remove("ch").unwrap_or(default_kit.cymbal),
+        })
+    }
 }
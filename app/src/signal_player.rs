@@ -2,10 +2,20 @@ use cpal_sample_player::SamplePlayer;
 use std::mem;
 use synth_language::{BufferedSignal, SignalCtx};
 
+/// Levels of the samples sent to the audio device during a single call to `send_signal`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Meter {
+    pub peak: f32,
+    pub rms: f32,
+    /// number of samples whose magnitude exceeded the range of the audio device
+    pub clip_count: u32,
+}
+
 pub struct SignalPlayer {
     sample_player: SamplePlayer<f32>,
     sample_index: u64,
     recent_samples: Vec<f32>,
+    meter: Meter,
 }
 
 impl SignalPlayer {
@@ -14,12 +24,16 @@ impl SignalPlayer {
             sample_player: SamplePlayer::new_with_downsample(downsample)?,
             sample_index: 0,
             recent_samples: Default::default(),
+            meter: Default::default(),
         })
     }
 
     pub fn send_signal(&mut self, buffered_signal: &mut BufferedSignal<f32>) {
         self.recent_samples.clear();
         let sample_rate = self.sample_player.sample_rate();
+        let mut peak = 0_f32;
+        let mut sum_of_squares = 0_f64;
+        let mut clip_count = 0;
         self.sample_player.play_stream(|| {
             let ctx = SignalCtx {
                 sample_index: self.sample_index,
@@ -28,12 +42,31 @@ impl SignalPlayer {
             let sample = buffered_signal.sample(&ctx);
             self.recent_samples.push(sample);
             self.sample_index += 1;
+            peak = peak.max(sample.abs());
+            sum_of_squares += (sample * sample) as f64;
+            if sample.abs() > 1.0 {
+                clip_count += 1;
+            }
             sample
         });
+        let rms = if self.recent_samples.is_empty() {
+            0.0
+        } else {
+            (sum_of_squares / self.recent_samples.len() as f64).sqrt() as f32
+        };
+        self.meter = Meter {
+            peak,
+            rms,
+            clip_count,
+        };
     }
 
     pub fn swap_recent_samples(&mut self, buffer: &mut Vec<f32>) {
         buffer.clear();
         mem::swap(&mut self.recent_samples, buffer)
     }
+
+    pub fn meter(&self) -> Meter {
+        self.meter
+    }
 }
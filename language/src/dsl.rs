@@ -1,10 +1,16 @@
 use crate::{
-    signal::{BufferedSignal, Const, Sbool, Sf64, Su8, TriggerVar, Var},
+    music::{NoteName, Scale},
+    pattern::{Pattern, PatternError},
+    sample::Sample,
+    signal::{BufferedSignal, Const, Sbool, Sf64, Smoothing, Su8, TriggerVar, Var},
     synth_modules::{
-        adsr_envelope_lin_01, amplify, asr_envelope_lin_01, biquad_filter, clock, oscillator,
-        random_uniform, sample_and_hold, sample_player, sum, synth_sequencer, trigger_sequencer_8,
+        adsr_envelope_lin_01, amplify, arpeggiator, asr_envelope_lin_01, biquad_filter, clock,
+        distortion, drum, dynamics, envelope_follower, glide, granular, mod_matrix, oscillator,
+        plucked_string, quantize, random_uniform, rhythm, sample_and_hold, sample_player,
+        slew_limiter, slicer, step_sequencer, sum, synth_sequencer, trigger_sequencer_8,
         weighted_sum,
     },
+    tuning::Tuning,
     Waveform,
 };
 
@@ -17,6 +23,12 @@ pub fn var<T: Clone + 'static>(value: T) -> (BufferedSignal<T>, Var<T>) {
     (var.buffered_signal(), var)
 }
 
+/// Like `var` but the signal ramps to new values of the variable rather than jumping to them
+pub fn var_smoothed(value: f64, smoothing: Smoothing) -> (Sf64, Var<f64>) {
+    let var = Var::new(value);
+    (var.smoothed_signal(smoothing), var)
+}
+
 pub fn trigger_var() -> (Sbool, TriggerVar) {
     let var = TriggerVar::new();
     (var.buffered_signal(), var)
@@ -111,6 +123,23 @@ pub fn weighted_sum_const_pair(left_weight: f64, left: Sf64, right: Sf64) -> Sf6
     weighted_sum_pair(const_(left_weight), left, right)
 }
 
+pub use mod_matrix::Slot as ModMatrixSlot;
+
+/// Returns a signal for each destination, which is its offset plus the sum of the sources routed
+/// to it, each scaled by the amount of its slot
+pub fn mod_matrix(
+    sources: Vec<Sf64>,
+    destination_offsets: Vec<Sf64>,
+    slots: Vec<ModMatrixSlot>,
+) -> Vec<Sf64> {
+    use mod_matrix::*;
+    create(Props {
+        sources,
+        destination_offsets,
+        slots,
+    })
+}
+
 pub fn amplify(signal: Sf64, by: Sf64) -> Sf64 {
     use amplify::*;
     create(Props { signal, by })
@@ -193,14 +222,97 @@ pub fn sample_and_hold(signal: Sf64, trigger: Sbool) -> Sf64 {
     create(Props { signal, trigger })
 }
 
+pub fn slew_limiter(signal: Sf64, rise_per_second: Sf64, fall_per_second: Sf64) -> Sf64 {
+    use slew_limiter::*;
+    create(Props {
+        signal,
+        rise_per_second,
+        fall_per_second,
+    })
+}
+
+pub fn glide(frequency_hz: Sf64, gate: Sbool, seconds_per_octave: Sf64, legato_only: bool) -> Sf64 {
+    use glide::*;
+    create(Props {
+        frequency_hz,
+        gate,
+        seconds_per_octave,
+        legato_only,
+    })
+}
+
+pub use quantize::Output as QuantizeOutput;
+pub fn quantize(frequency_hz: Sf64, scale: Scale, root: NoteName) -> QuantizeOutput {
+    quantize_tuned(frequency_hz, scale, root, Tuning::default())
+}
+
+pub fn quantize_tuned(
+    frequency_hz: Sf64,
+    scale: Scale,
+    root: NoteName,
+    tuning: Tuning,
+) -> QuantizeOutput {
+    use quantize::*;
+    create(Props {
+        frequency_hz,
+        scale,
+        root,
+        tuning,
+    })
+}
+
+/// Quantizes a pitch signal measured in octaves, where 0 corresponds to `zero_hz` and each
+/// increase of 1 raises the pitch by an octave
+pub fn quantize_voct(octaves: Sf64, zero_hz: f64, scale: Scale, root: NoteName) -> QuantizeOutput {
+    quantize(octaves.map(move |x| zero_hz * x.exp2()), scale, root)
+}
+
 pub fn clock(frequency_hz: Sf64) -> Sbool {
     use clock::*;
     create(Props { frequency_hz })
 }
 
+pub fn clock_divide(clock: Sbool, divisor: Sf64, reset: Sbool) -> Sbool {
+    use clock::divide::*;
+    create(Props {
+        clock,
+        divisor,
+        reset,
+    })
+}
+
+pub fn clock_multiply(clock: Sbool, factor: Sf64, reset: Sbool) -> Sbool {
+    use clock::multiply::*;
+    create(Props {
+        clock,
+        factor,
+        reset,
+    })
+}
+
+pub fn swing(clock: Sbool, amount_01: Sf64) -> Sbool {
+    use clock::swing::*;
+    create(Props { clock, amount_01 })
+}
+
+pub use clock::transport::Output as TransportOutput;
+pub fn transport(bpm: Sf64, beats_per_bar: Sf64, steps_per_beat: Sf64) -> TransportOutput {
+    use clock::transport::*;
+    create(Props {
+        bpm,
+        beats_per_bar,
+        steps_per_beat,
+    })
+}
+
 pub fn random_uniform() -> Sf64 {
     use random_uniform::*;
-    create()
+    create(Props { seed: None })
+}
+
+pub fn random_uniform_seeded(seed: u64) -> Sf64 {
+    use random_uniform::*;
+    create(Props { seed: Some(seed) })
 }
 
 pub use synth_sequencer::{Output as SynthSequencerOutput, Step as SynthSequencerStep};
@@ -209,12 +321,358 @@ pub fn synth_sequencer(sequence: Vec<SynthSequencerStep>, clock: Sbool) -> Synth
     create(Props { sequence, clock })
 }
 
+pub use step_sequencer::{
+    Direction as StepSequencerDirection, Output as StepSequencerOutput, Step as StepSequencerStep,
+};
+pub fn step_sequencer(
+    sequence: Vec<StepSequencerStep>,
+    clock: Sbool,
+    length: Sf64,
+    direction: StepSequencerDirection,
+    seed: Option<u64>,
+) -> StepSequencerOutput {
+    use step_sequencer::*;
+    create(Props {
+        sequence,
+        clock,
+        length,
+        direction,
+        seed,
+    })
+}
+
+/// Converts a pattern into a sequence for `trigger_sequencer_8` with one step for each step of the
+/// pattern's grid. Bit `i` of a step is set if an event named `names[i]` starts on that step.
+/// Only the first 8 names may appear in the pattern.
+pub fn pattern_trigger_sequence(
+    pattern: &Pattern,
+    names: &[&str],
+) -> Result<Vec<Su8>, PatternError> {
+    let mut sequence = Vec::new();
+    for step in pattern.grid() {
+        let mut bits = 0_u8;
+        for event in step {
+            match names.iter().position(|&name| name == event.value) {
+                Some(i) if i < 8 => bits |= 1 << i,
+                Some(_) => {
+                    return Err(PatternError {
+                        position: event.position,
+                        message: format!("at most 8 names may be used: {}", event.value),
+                    })
+                }
+                None => {
+                    return Err(PatternError {
+                        position: event.position,
+                        message: format!("unknown name: {}", event.value),
+                    })
+                }
+            }
+        }
+        sequence.push(const_(bits));
+    }
+    Ok(sequence)
+}
+
+/// Converts a pattern into a sequence for `synth_sequencer` with one step for each step of the
+/// pattern's grid. `frequency_hz` converts each event into a frequency. Only the first event to
+/// start on each step is played and the gate stays closed on steps where no event starts.
+pub fn pattern_synth_sequence<F: FnMut(&str) -> Option<f64>>(
+    pattern: &Pattern,
+    period_seconds: f64,
+    mut frequency_hz: F,
+) -> Result<Vec<SynthSequencerStep>, PatternError> {
+    let mut sequence = Vec::new();
+    let mut prev_frequency_hz = None;
+    let mut rests_at_start = 0;
+    for step in pattern.grid() {
+        if let Some(event) = step.first() {
+            let frequency_hz = match frequency_hz(&event.value) {
+                Some(frequency_hz) => frequency_hz,
+                None => {
+                    return Err(PatternError {
+                        position: event.position,
+                        message: format!("not a note: {}", event.value),
+                    })
+                }
+            };
+            prev_frequency_hz = Some(frequency_hz);
+            sequence.push(SynthSequencerStep {
+                frequency_hz: const_(frequency_hz),
+                period_seconds: const_(period_seconds),
+            });
+        } else if let Some(frequency_hz) = prev_frequency_hz {
+            // hold the previous frequency so the release of the previous note isn't affected
+            sequence.push(SynthSequencerStep {
+                frequency_hz: const_(frequency_hz),
+                period_seconds: const_(0.0),
+            });
+        } else {
+            rests_at_start += 1;
+        }
+    }
+    // the sequence loops, so rests at the start follow the last note
+    let last_frequency_hz = prev_frequency_hz.unwrap_or(0.0);
+    let rests = (0..rests_at_start).map(|_| SynthSequencerStep {
+        frequency_hz: const_(last_frequency_hz),
+        period_seconds: const_(0.0),
+    });
+    Ok(rests.chain(sequence).collect())
+}
+
+pub use arpeggiator::{
+    Note as ArpeggiatorNote, Order as ArpeggiatorOrder, Props as ArpeggiatorProps,
+};
+pub fn arpeggiator(props: ArpeggiatorProps) -> SynthSequencerOutput {
+    use arpeggiator::*;
+    create(props)
+}
+
 pub fn trigger_sequencer_8(sequence: Vec<Su8>, clock: Sbool) -> [Sbool; 8] {
     use trigger_sequencer_8::*;
     create(Props { sequence, clock })
 }
 
-pub fn sample_player(data: Vec<f32>, trigger: Sbool) -> Sf64 {
+pub fn euclidean(clock: Sbool, hits: Sf64, steps: Sf64, rotation: Sf64) -> Sbool {
+    use rhythm::euclidean::*;
+    create(Props {
+        clock,
+        hits,
+        steps,
+        rotation,
+    })
+}
+
+pub fn chance(trigger: Sbool, probability_01: Sf64, seed: Option<u64>) -> Sbool {
+    use rhythm::chance::*;
+    create(Props {
+        trigger,
+        probability_01,
+        seed,
+    })
+}
+
+pub fn ratchet(trigger: Sbool, count: Sf64, interval_seconds: Sf64) -> Sbool {
+    use rhythm::ratchet::*;
+    create(Props {
+        trigger,
+        count,
+        interval_seconds,
+    })
+}
+
+pub use rhythm::turing_machine::Output as TuringMachineOutput;
+pub fn turing_machine(
+    clock: Sbool,
+    length: Sf64,
+    flip_probability_01: Sf64,
+    seed: Option<u64>,
+) -> TuringMachineOutput {
+    use rhythm::turing_machine::*;
+    create(Props {
+        clock,
+        length,
+        flip_probability_01,
+        seed,
+    })
+}
+
+pub use sample_player::{LoopMode as SampleLoopMode, Props as SamplePlayerProps};
+pub fn sample_player(sample: Sample, trigger: Sbool) -> Sf64 {
+    sample_player_with_props(SamplePlayerProps::new(sample, trigger))
+}
+
+pub fn sample_player_with_props(props: SamplePlayerProps) -> Sf64 {
     use sample_player::*;
-    create(Props { data, trigger }).f64()
+    create(props)
+}
+
+pub use slicer::{Props as SlicerProps, Slices as SlicerSlices};
+pub fn slicer(sample: Sample, trigger: Sbool, index: Sf64, num_slices: usize) -> Sf64 {
+    slicer_with_props(SlicerProps {
+        sample,
+        trigger,
+        index,
+        slices: SlicerSlices::Equal(num_slices),
+        playback_rate: const_(1.0),
+    })
+}
+
+pub fn slicer_transients(sample: Sample, trigger: Sbool, index: Sf64, rise_db: f64) -> Sf64 {
+    slicer_with_props(SlicerProps {
+        sample,
+        trigger,
+        index,
+        slices: SlicerSlices::Transients { rise_db },
+        playback_rate: const_(1.0),
+    })
+}
+
+pub fn slicer_with_props(props: SlicerProps) -> Sf64 {
+    use slicer::*;
+    create(props)
+}
+
+pub use distortion::waveshaper::Curve as WaveshaperCurve;
+pub fn waveshaper(
+    signal: Sf64,
+    curve: WaveshaperCurve,
+    drive: Sf64,
+    mix_01: Sf64,
+    oversample: usize,
+) -> Sf64 {
+    use distortion::waveshaper::*;
+    create(Props {
+        signal,
+        curve,
+        drive,
+        mix_01,
+        oversample,
+    })
+}
+
+pub fn bitcrusher(
+    signal: Sf64,
+    bits: Sf64,
+    sample_rate_hz: Sf64,
+    mix_01: Sf64,
+    oversample: usize,
+) -> Sf64 {
+    use distortion::bitcrusher::*;
+    create(Props {
+        signal,
+        bits,
+        sample_rate_hz,
+        mix_01,
+        oversample,
+    })
+}
+
+pub use dynamics::compressor::Props as CompressorProps;
+pub fn compressor(props: CompressorProps) -> Sf64 {
+    use dynamics::compressor::*;
+    create(props)
+}
+
+pub fn limiter(
+    signal: Sf64,
+    ceiling_db: Sf64,
+    lookahead_seconds: f64,
+    release_seconds: Sf64,
+) -> Sf64 {
+    use dynamics::limiter::*;
+    create(Props {
+        signal,
+        ceiling_db,
+        lookahead_seconds,
+        release_seconds,
+    })
+}
+
+pub fn noise_gate(
+    signal: Sf64,
+    threshold_db: Sf64,
+    attack_seconds: Sf64,
+    release_seconds: Sf64,
+) -> Sf64 {
+    use dynamics::noise_gate::*;
+    create(Props {
+        signal,
+        threshold_db,
+        attack_seconds,
+        release_seconds,
+    })
+}
+
+pub use envelope_follower::Mode as EnvelopeFollowerMode;
+pub fn envelope_follower(
+    signal: Sf64,
+    mode: EnvelopeFollowerMode,
+    attack_seconds: Sf64,
+    release_seconds: Sf64,
+) -> Sf64 {
+    use envelope_follower::*;
+    create(Props {
+        signal,
+        mode,
+        attack_seconds,
+        release_seconds,
+    })
+}
+
+pub fn plucked_string(
+    frequency_hz: Sf64,
+    trigger: Sbool,
+    decay_seconds: Sf64,
+    brightness_01: Sf64,
+) -> Sf64 {
+    use plucked_string::*;
+    create(Props {
+        frequency_hz,
+        trigger,
+        excitation: Excitation::Noise,
+        decay_seconds,
+        brightness_01,
+    })
+}
+
+/// Plucked string which is continuously fed the `excitation` signal rather than being triggered
+pub fn plucked_string_excited(
+    frequency_hz: Sf64,
+    excitation: Sf64,
+    decay_seconds: Sf64,
+    brightness_01: Sf64,
+) -> Sf64 {
+    use plucked_string::*;
+    create(Props {
+        frequency_hz,
+        trigger: const_(false),
+        excitation: Excitation::Signal(excitation),
+        decay_seconds,
+        brightness_01,
+    })
+}
+
+pub fn kick_drum(trigger: Sbool, frequency_hz: Sf64, sweep_hz: Sf64, decay_seconds: Sf64) -> Sf64 {
+    use drum::kick::*;
+    create(Props {
+        trigger,
+        frequency_hz,
+        sweep_hz,
+        decay_seconds,
+    })
+}
+
+pub fn snare_drum(trigger: Sbool, frequency_hz: Sf64, decay_seconds: Sf64, tone_01: Sf64) -> Sf64 {
+    use drum::snare::*;
+    create(Props {
+        trigger,
+        frequency_hz,
+        decay_seconds,
+        tone_01,
+    })
+}
+
+pub fn hi_hat(trigger: Sbool, frequency_hz: Sf64, decay_seconds: Sf64, tone_hz: Sf64) -> Sf64 {
+    use drum::hi_hat::*;
+    create(Props {
+        trigger,
+        frequency_hz,
+        decay_seconds,
+        tone_hz,
+    })
+}
+
+pub fn clap(trigger: Sbool, decay_seconds: Sf64, tone_hz: Sf64) -> Sf64 {
+    use drum::clap::*;
+    create(Props {
+        trigger,
+        decay_seconds,
+        tone_hz,
+    })
+}
+
+pub use granular::Props as GranularProps;
+pub fn granular(props: GranularProps) -> Sf64 {
+    use granular::*;
+    create(props)
 }
@@ -1,6 +1,12 @@
 mod dsl;
+mod mod_matrix;
+pub mod music;
+mod param;
+mod pattern;
+mod sample;
 mod signal;
 mod synth_modules;
+pub mod tuning;
 
 #[derive(Debug, Clone, Copy)]
 pub enum Waveform {
@@ -11,6 +17,10 @@ pub enum Waveform {
 }
 
 pub use dsl::*;
+pub use mod_matrix::ModMatrix;
+pub use param::{Curve as ParamCurve, Param, ParamRegistry, ParamSpec, Unit as ParamUnit};
+pub use pattern::{Event as PatternEvent, Pattern, PatternError, Rational};
+pub use sample::{load_wav_dir, Sample, SampleLoadError};
 pub use signal::{
-    BoolVar, BufferedSignal, Sbool, Sf32, Sf64, SignalCtx, SignalTrait, TriggerVar, Var,
+    BoolVar, BufferedSignal, Sbool, Sf32, Sf64, SignalCtx, SignalTrait, Smoothing, TriggerVar, Var,
 };
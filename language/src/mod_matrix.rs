@@ -0,0 +1,176 @@
+//! Modulation matrix whose amounts are variables, so that routing can be changed while the synth
+//! is running and saved along with other parameters
+use crate::{
+    param::{ParamRegistry, ParamSpec},
+    signal::{Sf64, Var},
+    synth_modules::mod_matrix,
+};
+
+struct Amount {
+    spec: ParamSpec,
+    var: Var<f64>,
+    /// shared by every instance of the matrix so that smoothing happens once per amount
+    signal: Sf64,
+}
+
+/// Amounts by which each source is added to each destination. All instances of the matrix
+/// share the same amounts.
+pub struct ModMatrix {
+    name: String,
+    sources: Vec<String>,
+    destinations: Vec<String>,
+    /// indexed by `(source_index * destinations.len()) + destination_index`
+    amounts: Vec<Amount>,
+}
+
+impl ModMatrix {
+    /// Matrix where each destination is paired with the spec of the amounts by which sources are
+    /// added to it. Each amount is initialized to the default of its spec.
+    pub fn new(
+        name: impl Into<String>,
+        sources: &[&str],
+        destinations: &[(&str, ParamSpec)],
+    ) -> Self {
+        let amounts = sources
+            .iter()
+            .flat_map(|_| destinations.iter())
+            .map(|(_, spec)| {
+                let var = Var::new(spec.default);
+                let signal = match spec.smoothing {
+                    Some(smoothing) => var.smoothed_signal(smoothing),
+                    None => var.buffered_signal(),
+                };
+                Amount {
+                    spec: spec.clone(),
+                    var,
+                    signal,
+                }
+            })
+            .collect();
+        Self {
+            name: name.into(),
+            sources: sources.iter().map(|s| s.to_string()).collect(),
+            destinations: destinations.iter().map(|(d, _)| d.to_string()).collect(),
+            amounts,
+        }
+    }
+
+    fn index(&self, source: &str, destination: &str) -> Option<usize> {
+        let source_index = self.sources.iter().position(|s| s == source)?;
+        let destination_index = self.destinations.iter().position(|d| d == destination)?;
+        Some((source_index * self.destinations.len()) + destination_index)
+    }
+
+    /// Sets the amount (and its default) by which a source is added to a destination. Panics if
+    /// the source or destination doesn't exist.
+    pub fn with_route(mut self, source: &str, destination: &str, amount: f64) -> Self {
+        let index = self
+            .index(source, destination)
+            .unwrap_or_else(|| panic!("no route from {} to {}", source, destination));
+        let slot = &mut self.amounts[index];
+        slot.spec.default = slot.spec.clamp(amount);
+        slot.var.set(slot.spec.default);
+        self
+    }
+
+    pub fn sources(&self) -> &[String] {
+        &self.sources
+    }
+
+    pub fn destinations(&self) -> &[String] {
+        &self.destinations
+    }
+
+    pub fn amount(&self, source: &str, destination: &str) -> Option<&Var<f64>> {
+        self.index(source, destination)
+            .map(|index| &self.amounts[index].var)
+    }
+
+    /// Name of the parameter controlling an amount when the matrix is registered, which is
+    /// "<matrix>/<source>/<destination>"
+    pub fn param_name(&self, source: &str, destination: &str) -> String {
+        format!("{}/{}/{}", self.name, source, destination)
+    }
+
+    /// Registers each amount as a parameter
+    pub fn register(&self, params: &mut ParamRegistry) {
+        for (i, amount) in self.amounts.iter().enumerate() {
+            let source = &self.sources[i / self.destinations.len()];
+            let destination = &self.destinations[i % self.destinations.len()];
+            params.register_var(
+                self.param_name(source, destination),
+                amount.var.clone_ref(),
+                amount.spec.clone(),
+            );
+        }
+    }
+
+    /// Creates an instance of the matrix, returning a signal for each destination. The sources
+    /// and the value of each destination before any sources are added to it must be given in the
+    /// order that their names were passed to `new`.
+    pub fn create(&self, sources: Vec<Sf64>, destination_offsets: Vec<Sf64>) -> Vec<Sf64> {
+        assert_eq!(sources.len(), self.sources.len(), "wrong number of sources");
+        assert_eq!(
+            destination_offsets.len(),
+            self.destinations.len(),
+            "wrong number of destinations"
+        );
+        let slots = self
+            .amounts
+            .iter()
+            .enumerate()
+            .map(|(i, amount)| mod_matrix::Slot {
+                source: i / self.destinations.len(),
+                destination: i % self.destinations.len(),
+                amount: amount.signal.clone_ref(),
+            })
+            .collect();
+        mod_matrix::create(mod_matrix::Props {
+            sources,
+            destination_offsets,
+            slots,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{dsl::const_, signal::SignalCtx};
+
+    #[test]
+    fn routes() {
+        let matrix = ModMatrix::new(
+            "m",
+            &["a", "b"],
+            &[
+                ("x", ParamSpec::new(-10.0, 10.0, 0.0)),
+                ("y", ParamSpec::new(-10.0, 10.0, 0.0)),
+            ],
+        )
+        .with_route("a", "x", 2.0)
+        .with_route("b", "x", 3.0)
+        .with_route("b", "y", -1.0);
+        let mut params = ParamRegistry::new();
+        matrix.register(&mut params);
+        let create = || {
+            matrix.create(
+                vec![const_(1.0), const_(10.0)],
+                vec![const_(100.0), const_(0.0)],
+            )
+        };
+        let mut first = create();
+        let mut second = create();
+        let mut ctx = SignalCtx {
+            sample_index: 0,
+            sample_rate: 44100,
+        };
+        assert_eq!(first[0].sample(&ctx), 132.0);
+        assert_eq!(first[1].sample(&ctx), -10.0);
+        // amounts are shared by every instance
+        assert!(params.set(&matrix.param_name("a", "y"), 5.0));
+        ctx.sample_index += 1;
+        assert_eq!(second[1].sample(&ctx), -5.0);
+        assert_eq!(first[1].sample(&ctx), -5.0);
+    }
+}
@@ -0,0 +1,739 @@
+use std::{error, fmt, ops, str::FromStr};
+
+const SCALE_SIZE: usize = 12;
+const A4_FREQUENCY_HZ: f64 = 440.0;
+const A4_MIDI_NUMBER: i32 = 69;
+const DEFAULT_OCTAVE: i32 = 4;
+// Octaves of parsed notes, covering the range of MIDI note numbers
+const OCTAVES: ops::RangeInclusive<i32> = -1..=9;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    NoteName(String),
+    Note(String),
+    Scale(String),
+    Chord(String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoteName(s) => write!(f, "not a note name: {}", s),
+            Self::Note(s) => write!(f, "not a note: {}", s),
+            Self::Scale(s) => write!(f, "not a scale: {}", s),
+            Self::Chord(s) => write!(f, "not a chord: {}", s),
+        }
+    }
+}
+
+impl error::Error for ParseError {}
+
+pub fn note_frequency_even_temperement(base_freq: f64, key_offset: f64) -> f64 {
+    base_freq * (2_f64.powf(key_offset / 12_f64))
+}
+
+#[derive(Debug, Clone, Copy, PartialOrd, Ord, PartialEq, Eq, Hash)]
+pub enum NoteName {
+    C,
+    CSharp,
+    D,
+    DSharp,
+    E,
+    F,
+    FSharp,
+    G,
+    GSharp,
+    A,
+    ASharp,
+    B,
+}
+
+impl NoteName {
+    const ALL: [Self; SCALE_SIZE] = [
+        Self::C,
+        Self::CSharp,
+        Self::D,
+        Self::DSharp,
+        Self::E,
+        Self::F,
+        Self::FSharp,
+        Self::G,
+        Self::GSharp,
+        Self::A,
+        Self::ASharp,
+        Self::B,
+    ];
+
+    /// Number of semitones above C
+    pub fn semitones(self) -> i32 {
+        use NoteName::*;
+        match self {
+            C => 0,
+            CSharp => 1,
+            D => 2,
+            DSharp => 3,
+            E => 4,
+            F => 5,
+            FSharp => 6,
+            G => 7,
+            GSharp => 8,
+            A => 9,
+            ASharp => 10,
+            B => 11,
+        }
+    }
+
+    fn from_semitones(semitones: i32) -> Self {
+        Self::ALL[semitones.rem_euclid(SCALE_SIZE as i32) as usize]
+    }
+
+    pub fn frequency_in_octave(self, octave: i32) -> f64 {
+        note(self, octave).frequency()
+    }
+}
+
+impl fmt::Display for NoteName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use NoteName::*;
+        let str = match self {
+            A => "a",
+            ASharp => "a-sharp",
+            B => "b",
+            C => "c",
+            CSharp => "c-sharp",
+            D => "d",
+            DSharp => "d-sharp",
+            E => "e",
+            F => "f",
+            FSharp => "f-sharp",
+            G => "g",
+            GSharp => "g-sharp",
+        };
+        write!(f, "{}", str)
+    }
+}
+
+// Parses a note name from the start of a string, returning the name, the number of octaves its
+// octave number is offset by (e.g. "cb4" is a B in octave 3) and the rest of the string. A note name
+// is a letter optionally followed by an accidental such as "#", "b", "-sharp" or "-flat".
+fn split_note_name(s: &str) -> Option<(NoteName, i32, &str)> {
+    let mut chars = s.chars();
+    let natural = match chars.next()?.to_ascii_lowercase() {
+        'c' => NoteName::C,
+        'd' => NoteName::D,
+        'e' => NoteName::E,
+        'f' => NoteName::F,
+        'g' => NoteName::G,
+        'a' => NoteName::A,
+        'b' => NoteName::B,
+        _ => return None,
+    };
+    let rest = chars.as_str();
+    let accidentals = [
+        ("-sharp", 1),
+        ("-flat", -1),
+        ("sharp", 1),
+        ("flat", -1),
+        ("#", 1),
+        ("\u{266F}", 1),
+        ("b", -1),
+        ("\u{266D}", -1),
+    ];
+    let (rest, accidental) = accidentals
+        .iter()
+        .find_map(|&(prefix, accidental)| rest.strip_prefix(prefix).map(|rest| (rest, accidental)))
+        .unwrap_or((rest, 0));
+    let semitones = natural.semitones() + accidental;
+    Some((
+        NoteName::from_semitones(semitones),
+        semitones.div_euclid(SCALE_SIZE as i32),
+        rest,
+    ))
+}
+
+impl FromStr for NoteName {
+    type Err = ParseError;
+
+    /// Parses names such as "c", "C#", "Db", "c-sharp" or "a-flat"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match split_note_name(s) {
+            Some((name, _, "")) => Ok(name),
+            _ => Err(ParseError::NoteName(s.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Interval {
+    Unison,
+    MinorSecond,
+    MajorSecond,
+    MinorThird,
+    MajorThird,
+    PerfectFourth,
+    Tritone,
+    PerfectFifth,
+    MinorSixth,
+    MajorSixth,
+    MinorSeventh,
+    MajorSeventh,
+    Octave,
+}
+
+impl Interval {
+    pub fn semitones(self) -> i32 {
+        use Interval::*;
+        match self {
+            Unison => 0,
+            MinorSecond => 1,
+            MajorSecond => 2,
+            MinorThird => 3,
+            MajorThird => 4,
+            PerfectFourth => 5,
+            Tritone => 6,
+            PerfectFifth => 7,
+            MinorSixth => 8,
+            MajorSixth => 9,
+            MinorSeventh => 10,
+            MajorSeventh => 11,
+            Octave => 12,
+        }
+    }
+}
+
+/// A note in scientific pitch notation, where octaves start at C and middle C is in octave 4
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Note {
+    pub name: NoteName,
+    pub octave: i32,
+}
+
+impl Note {
+    pub fn new(name: NoteName, octave: i32) -> Self {
+        Self { name, octave }
+    }
+
+    /// Frequency in 12-tone equal temperament where A4 is 440Hz
+    pub fn frequency(&self) -> f64 {
+        note_frequency_even_temperement(
+            A4_FREQUENCY_HZ,
+            (self.midi_number() - A4_MIDI_NUMBER) as f64,
+        )
+    }
+
+    /// The MIDI note number of this note, where middle C (C4) is 60
+    pub fn midi_number(&self) -> i32 {
+        ((self.octave + 1) * SCALE_SIZE as i32) + self.name.semitones()
+    }
+
+    pub fn from_midi_number(midi_number: i32) -> Self {
+        Self {
+            name: NoteName::from_semitones(midi_number),
+            octave: midi_number.div_euclid(SCALE_SIZE as i32) - 1,
+        }
+    }
+
+    /// The note `semitones` semitones above this note (or below if negative)
+    pub fn transpose(&self, semitones: i32) -> Self {
+        Self::from_midi_number(self.midi_number() + semitones)
+    }
+}
+
+impl ops::Add<Interval> for Note {
+    type Output = Self;
+    fn add(self, rhs: Interval) -> Self {
+        self.transpose(rhs.semitones())
+    }
+}
+
+impl ops::Sub<Interval> for Note {
+    type Output = Self;
+    fn sub(self, rhs: Interval) -> Self {
+        self.transpose(-rhs.semitones())
+    }
+}
+
+impl fmt::Display for Note {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.name, self.octave)
+    }
+}
+
+impl FromStr for Note {
+    type Err = ParseError;
+
+    /// Parses a note name followed by an octave (e.g. "C#4", "Db3", "a-flat2", "c-1") or a MIDI
+    /// note number from 0 to 127 (e.g. "60"). Notes without an octave are in octave 4. Octaves
+    /// must be between -1 and 9.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseError::Note(s.to_string());
+        if !s.is_empty() && s.chars().all(|ch| ch.is_ascii_digit()) {
+            return match s.parse::<i32>() {
+                Ok(midi_number @ 0..=127) => Ok(Self::from_midi_number(midi_number)),
+                _ => Err(err()),
+            };
+        }
+        let (name, octave_offset, octave) = split_note_name(s).ok_or_else(err)?;
+        let octave = if octave.is_empty() {
+            DEFAULT_OCTAVE
+        } else {
+            octave.parse::<i32>().map_err(|_| err())?
+        };
+        let octave = octave.checked_add(octave_offset).ok_or_else(err)?;
+        if !OCTAVES.contains(&octave) {
+            return Err(err());
+        }
+        Ok(Self { name, octave })
+    }
+}
+
+pub fn note(name: NoteName, octave: i32) -> Note {
+    Note { name, octave }
+}
+
+/// A set of notes within an octave, represented as the number of semitones of each note above the
+/// root of the scale
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scale {
+    semitones: Vec<u32>,
+}
+
+impl Scale {
+    /// The root (0) is always included, and semitones are wrapped into a single octave
+    pub fn new(semitones: &[u32]) -> Self {
+        let mut semitones = semitones
+            .iter()
+            .map(|&x| x % SCALE_SIZE as u32)
+            .chain([0])
+            .collect::<Vec<_>>();
+        semitones.sort_unstable();
+        semitones.dedup();
+        Self { semitones }
+    }
+
+    /// Scale described by the number of semitones between consecutive notes starting at the root,
+    /// e.g. the major scale is 2, 2, 1, 2, 2, 2, 1
+    pub fn from_intervals(intervals: &[u32]) -> Self {
+        let semitones = intervals
+            .iter()
+            .scan(0, |acc, &interval| {
+                let semitone = *acc;
+                *acc += interval;
+                Some(semitone)
+            })
+            .collect::<Vec<_>>();
+        Self::new(&semitones)
+    }
+
+    pub fn major() -> Self {
+        Self::from_intervals(&[2, 2, 1, 2, 2, 2, 1])
+    }
+
+    /// The `n`th mode of the major scale, where the 0th mode is the major scale itself
+    fn major_mode(n: usize) -> Self {
+        let mut intervals = [2, 2, 1, 2, 2, 2, 1];
+        intervals.rotate_left(n % 7);
+        Self::from_intervals(&intervals)
+    }
+
+    pub fn dorian() -> Self {
+        Self::major_mode(1)
+    }
+
+    pub fn phrygian() -> Self {
+        Self::major_mode(2)
+    }
+
+    pub fn lydian() -> Self {
+        Self::major_mode(3)
+    }
+
+    pub fn mixolydian() -> Self {
+        Self::major_mode(4)
+    }
+
+    /// Natural minor scale (aeolian mode)
+    pub fn minor() -> Self {
+        Self::major_mode(5)
+    }
+
+    pub fn locrian() -> Self {
+        Self::major_mode(6)
+    }
+
+    pub fn harmonic_minor() -> Self {
+        Self::from_intervals(&[2, 1, 2, 2, 1, 3, 1])
+    }
+
+    /// Ascending melodic minor scale
+    pub fn melodic_minor() -> Self {
+        Self::from_intervals(&[2, 1, 2, 2, 2, 2, 1])
+    }
+
+    pub fn major_pentatonic() -> Self {
+        Self::new(&[0, 2, 4, 7, 9])
+    }
+
+    pub fn minor_pentatonic() -> Self {
+        Self::new(&[0, 3, 5, 7, 10])
+    }
+
+    pub fn blues() -> Self {
+        Self::new(&[0, 3, 5, 6, 7, 10])
+    }
+
+    pub fn chromatic() -> Self {
+        Self::new(&(0..SCALE_SIZE as u32).collect::<Vec<_>>())
+    }
+
+    pub fn semitones(&self) -> &[u32] {
+        &self.semitones
+    }
+
+    /// Number of notes in each octave of the scale
+    pub fn len(&self) -> usize {
+        self.semitones.len()
+    }
+
+    /// Always false as every scale contains its root
+    pub fn is_empty(&self) -> bool {
+        self.semitones.is_empty()
+    }
+
+    /// Number of semitones above the root of the note at a scale degree, where degree 0 is the
+    /// root. Degrees beyond the length of the scale continue into higher or lower octaves.
+    pub fn degree_semitones(&self, degree: i32) -> i32 {
+        let len = self.len() as i32;
+        let octave = degree.div_euclid(len);
+        let index = degree.rem_euclid(len) as usize;
+        (octave * SCALE_SIZE as i32) + self.semitones[index] as i32
+    }
+
+    /// The note at a scale degree in the scale starting at `root`
+    pub fn note(&self, root: Note, degree: i32) -> Note {
+        root.transpose(self.degree_semitones(degree))
+    }
+}
+
+impl FromStr for Scale {
+    type Err = ParseError;
+
+    /// Parses the name of a scale (e.g. "major", "harmonic-minor", "minor-pentatonic") or a
+    /// comma-separated list of semitones above the root (e.g. "0,2,3,7,8")
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let scale = match s {
+            "major" | "ionian" => Self::major(),
+            "dorian" => Self::dorian(),
+            "phrygian" => Self::phrygian(),
+            "lydian" => Self::lydian(),
+            "mixolydian" => Self::mixolydian(),
+            "minor" | "aeolian" => Self::minor(),
+            "locrian" => Self::locrian(),
+            "harmonic-minor" => Self::harmonic_minor(),
+            "melodic-minor" => Self::melodic_minor(),
+            "major-pentatonic" => Self::major_pentatonic(),
+            "minor-pentatonic" => Self::minor_pentatonic(),
+            "blues" => Self::blues(),
+            "chromatic" => Self::chromatic(),
+            _ => {
+                let semitones = s
+                    .split(',')
+                    .map(|x| x.trim().parse::<u32>())
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|_| ParseError::Scale(s.to_string()))?;
+                Self::new(&semitones)
+            }
+        };
+        Ok(scale)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChordQuality {
+    Major,
+    Minor,
+    Diminished,
+    Augmented,
+    Sus2,
+    Sus4,
+    Major7,
+    Minor7,
+    Dominant7,
+    Diminished7,
+    HalfDiminished7,
+    MinorMajor7,
+}
+
+impl ChordQuality {
+    const ALL: [Self; 12] = [
+        Self::Major,
+        Self::Minor,
+        Self::Diminished,
+        Self::Augmented,
+        Self::Sus2,
+        Self::Sus4,
+        Self::Major7,
+        Self::Minor7,
+        Self::Dominant7,
+        Self::Diminished7,
+        Self::HalfDiminished7,
+        Self::MinorMajor7,
+    ];
+
+    /// Number of semitones above the root of each note of the chord
+    pub fn semitones(self) -> &'static [u32] {
+        use ChordQuality::*;
+        match self {
+            Major => &[0, 4, 7],
+            Minor => &[0, 3, 7],
+            Diminished => &[0, 3, 6],
+            Augmented => &[0, 4, 8],
+            Sus2 => &[0, 2, 7],
+            Sus4 => &[0, 5, 7],
+            Major7 => &[0, 4, 7, 11],
+            Minor7 => &[0, 3, 7, 10],
+            Dominant7 => &[0, 4, 7, 10],
+            Diminished7 => &[0, 3, 6, 9],
+            HalfDiminished7 => &[0, 3, 6, 10],
+            MinorMajor7 => &[0, 3, 7, 11],
+        }
+    }
+
+    /// Suffix following the root in the name of a chord, e.g. "m7" in "am7"
+    pub fn suffix(self) -> &'static str {
+        use ChordQuality::*;
+        match self {
+            Major => "",
+            Minor => "m",
+            Diminished => "dim",
+            Augmented => "aug",
+            Sus2 => "sus2",
+            Sus4 => "sus4",
+            Major7 => "maj7",
+            Minor7 => "m7",
+            Dominant7 => "7",
+            Diminished7 => "dim7",
+            HalfDiminished7 => "m7b5",
+            MinorMajor7 => "mmaj7",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Chord {
+    pub root: NoteName,
+    pub quality: ChordQuality,
+    /// number of notes from the bottom of the chord which are moved up an octave
+    pub inversion: usize,
+}
+
+impl Chord {
+    pub fn new(root: NoteName, quality: ChordQuality) -> Self {
+        Self {
+            root,
+            quality,
+            inversion: 0,
+        }
+    }
+
+    pub fn with_inversion(self, inversion: usize) -> Self {
+        Self { inversion, ..self }
+    }
+
+    /// Number of semitones of each note of the chord above its root, from lowest to highest
+    pub fn semitones(&self) -> Vec<u32> {
+        let semitones = self.quality.semitones();
+        (0..semitones.len())
+            .map(|i| {
+                let i = i + self.inversion;
+                let octave = (i / semitones.len()) as u32;
+                semitones[i % semitones.len()] + (octave * SCALE_SIZE as u32)
+            })
+            .collect()
+    }
+
+    /// Notes of the chord where the root is in the given octave
+    pub fn notes(&self, octave: i32) -> Vec<Note> {
+        let root = note(self.root, octave);
+        self.semitones()
+            .into_iter()
+            .map(|semitones| root.transpose(semitones as i32))
+            .collect()
+    }
+}
+
+impl fmt::Display for Chord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.root, self.quality.suffix())?;
+        if self.inversion > 0 {
+            write!(f, "/{}", self.inversion)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Chord {
+    type Err = ParseError;
+
+    /// Parses a root note followed by a quality suffix and an optional inversion, e.g. "c", "Bbm",
+    /// "a-sharpm7" or "F#maj7/1"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseError::Chord(s.to_string());
+        let (name, inversion) = match s.split_once('/') {
+            Some((name, inversion)) => (name, inversion.parse::<usize>().map_err(|_| err())?),
+            None => (s, 0),
+        };
+        let (root, _, suffix) = split_note_name(name).ok_or_else(err)?;
+        // the suffix is case sensitive to distinguish "M7" from "m7"
+        let quality = match suffix {
+            "M" | "maj" => ChordQuality::Major,
+            "M7" => ChordQuality::Major7,
+            "min" => ChordQuality::Minor,
+            "min7" => ChordQuality::Minor7,
+            _ => ChordQuality::ALL
+                .into_iter()
+                .find(|quality| quality.suffix() == suffix)
+                .ok_or_else(err)?,
+        };
+        Ok(Self {
+            root,
+            quality,
+            inversion,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_note_name() {
+        assert_eq!("c".parse(), Ok(NoteName::C));
+        assert_eq!("C#".parse(), Ok(NoteName::CSharp));
+        assert_eq!("Db".parse(), Ok(NoteName::CSharp));
+        assert_eq!("c-sharp".parse(), Ok(NoteName::CSharp));
+        assert_eq!("a-flat".parse(), Ok(NoteName::GSharp));
+        assert_eq!("B♭".parse(), Ok(NoteName::ASharp));
+        assert_eq!("cb".parse(), Ok(NoteName::B));
+        assert!("h".parse::<NoteName>().is_err());
+        assert!("c4".parse::<NoteName>().is_err());
+        assert!("".parse::<NoteName>().is_err());
+    }
+
+    #[test]
+    fn parse_note() {
+        assert_eq!("C4".parse(), Ok(note(NoteName::C, 4)));
+        assert_eq!("c".parse(), Ok(note(NoteName::C, 4)));
+        assert_eq!("Db3".parse(), Ok(note(NoteName::CSharp, 3)));
+        assert_eq!("a-flat3".parse(), Ok(note(NoteName::GSharp, 3)));
+        assert_eq!("c-1".parse(), Ok(note(NoteName::C, -1)));
+        assert_eq!("g9".parse(), Ok(note(NoteName::G, 9)));
+        // accidentals which cross a C move the note into the neighbouring octave
+        assert_eq!("cb4".parse(), Ok(note(NoteName::B, 3)));
+        assert_eq!("b#4".parse(), Ok(note(NoteName::C, 5)));
+    }
+
+    #[test]
+    fn parse_midi_number() {
+        assert_eq!("60".parse(), Ok(note(NoteName::C, 4)));
+        assert_eq!("69".parse(), Ok(note(NoteName::A, 4)));
+        assert_eq!("0".parse(), Ok(note(NoteName::C, -1)));
+        assert_eq!("127".parse(), Ok(note(NoteName::G, 9)));
+        assert!("128".parse::<Note>().is_err());
+        assert!("99999999999".parse::<Note>().is_err());
+    }
+
+    #[test]
+    fn parse_invalid_note() {
+        for s in [
+            "",
+            "h4",
+            "c4x",
+            "c#b4",
+            "c 4",
+            "-1",
+            "c10",
+            "c-2",
+            "cb-1",
+            "c2147483647",
+            "c-2147483648",
+        ] {
+            assert_eq!(
+                s.parse::<Note>(),
+                Err(ParseError::Note(s.to_string())),
+                "{s}"
+            );
+        }
+    }
+
+    #[test]
+    fn midi_number_round_trip() {
+        for midi_number in 0..=127 {
+            let note = Note::from_midi_number(midi_number);
+            assert_eq!(note.midi_number(), midi_number);
+            assert_eq!(note.to_string().parse(), Ok(note));
+        }
+    }
+
+    #[test]
+    fn frequency() {
+        assert_eq!(note(NoteName::A, 4).frequency(), 440.0);
+        assert_eq!(note(NoteName::A, 3).frequency(), 220.0);
+    }
+
+    #[test]
+    fn transpose() {
+        let c4 = note(NoteName::C, 4);
+        assert_eq!(c4.transpose(0), c4);
+        assert_eq!(c4.transpose(-1), note(NoteName::B, 3));
+        assert_eq!(c4.transpose(14), note(NoteName::D, 5));
+        assert_eq!(c4 + Interval::PerfectFifth, note(NoteName::G, 4));
+        assert_eq!(c4 + Interval::Octave, note(NoteName::C, 5));
+        assert_eq!(c4 - Interval::MinorThird, note(NoteName::A, 3));
+        assert_eq!(c4 - Interval::Unison, c4);
+    }
+
+    #[test]
+    fn parse_scale() {
+        assert_eq!("major".parse(), Ok(Scale::major()));
+        assert_eq!("aeolian".parse(), Ok(Scale::minor()));
+        assert_eq!(
+            "0, 2,3,7,8".parse::<Scale>().unwrap().semitones(),
+            &[0, 2, 3, 7, 8]
+        );
+        assert!("majestic".parse::<Scale>().is_err());
+        assert!("0,2,-1".parse::<Scale>().is_err());
+    }
+
+    #[test]
+    fn parse_chord() {
+        assert_eq!(
+            "c".parse(),
+            Ok(Chord::new(NoteName::C, ChordQuality::Major))
+        );
+        assert_eq!(
+            "Bbm".parse(),
+            Ok(Chord::new(NoteName::ASharp, ChordQuality::Minor))
+        );
+        assert_eq!(
+            "a-sharpm7".parse(),
+            Ok(Chord::new(NoteName::ASharp, ChordQuality::Minor7))
+        );
+        assert_eq!(
+            "F#maj7/1".parse(),
+            Ok(Chord::new(NoteName::FSharp, ChordQuality::Major7).with_inversion(1))
+        );
+        assert_eq!(
+            "gM7".parse(),
+            Ok(Chord::new(NoteName::G, ChordQuality::Major7))
+        );
+        for s in ["", "x", "cmajor", "c/x"] {
+            assert_eq!(
+                s.parse::<Chord>(),
+                Err(ParseError::Chord(s.to_string())),
+                "{s}"
+            );
+        }
+    }
+}
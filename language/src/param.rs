@@ -0,0 +1,338 @@
+//! Named parameters with ranges and units, so that controllers (the UI, OSC, presets, ...) can
+//! find and change parameters without knowing which part of the synth they control
+use crate::signal::{Exp01, Sf64, Smoothing, Var};
+use std::{collections::HashMap, fmt};
+
+/// How normalized values from 0 to 1 are mapped onto the range of a parameter
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Curve {
+    Linear,
+    /// Exponential curve whose sharpness is determined by `k` (as in `exp01`), where larger
+    /// values of `k` allocate more of the normalized range to the low end of the parameter. `k`
+    /// must be non-negative, and 0 is equivalent to a linear curve.
+    Exponential(f64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unit {
+    None,
+    Hz,
+    Seconds,
+    Decibels,
+}
+
+impl Unit {
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Self::None => "",
+            Self::Hz => "Hz",
+            Self::Seconds => "s",
+            Self::Decibels => "dB",
+        }
+    }
+}
+
+impl fmt::Display for Unit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.symbol())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParamSpec {
+    pub min: f64,
+    pub max: f64,
+    pub default: f64,
+    pub curve: Curve,
+    pub unit: Unit,
+    /// if set, signals of the parameter ramp to new values rather than jumping to them
+    pub smoothing: Option<Smoothing>,
+}
+
+impl ParamSpec {
+    /// Linear parameter with no unit
+    pub fn new(min: f64, max: f64, default: f64) -> Self {
+        Self {
+            min,
+            max,
+            default,
+            curve: Curve::Linear,
+            unit: Unit::None,
+            smoothing: None,
+        }
+    }
+
+    pub fn with_curve(self, curve: Curve) -> Self {
+        Self { curve, ..self }
+    }
+
+    pub fn with_unit(self, unit: Unit) -> Self {
+        Self { unit, ..self }
+    }
+
+    pub fn with_smoothing(self, smoothing: Smoothing) -> Self {
+        Self {
+            smoothing: Some(smoothing),
+            ..self
+        }
+    }
+
+    pub fn clamp(&self, value: f64) -> f64 {
+        value.clamp(self.min.min(self.max), self.max.max(self.min))
+    }
+
+    fn exp01(&self) -> Exp01 {
+        match self.curve {
+            Curve::Linear => Exp01::new(0.0),
+            Curve::Exponential(k) => Exp01::new(k),
+        }
+    }
+
+    /// Maps a value in the range of the parameter to a value from 0 to 1
+    pub fn normalize(&self, value: f64) -> f64 {
+        let range = self.max - self.min;
+        if range == 0.0 {
+            return 0.0;
+        }
+        let linear_01 = ((self.clamp(value) - self.min) / range).clamp(0.0, 1.0);
+        self.exp01().inverse(linear_01).clamp(0.0, 1.0)
+    }
+
+    /// Maps a value from 0 to 1 to a value in the range of the parameter
+    pub fn denormalize(&self, value_01: f64) -> f64 {
+        let linear_01 = self.exp01().get(value_01.clamp(0.0, 1.0));
+        self.clamp(self.min + (linear_01 * (self.max - self.min)))
+    }
+}
+
+pub struct Param {
+    name: String,
+    spec: ParamSpec,
+    var: Var<f64>,
+}
+
+impl Param {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn spec(&self) -> &ParamSpec {
+        &self.spec
+    }
+
+    pub fn var(&self) -> &Var<f64> {
+        &self.var
+    }
+
+    pub fn get(&self) -> f64 {
+        self.var.get()
+    }
+
+    /// Values outside the range of the parameter are clamped, and values which aren't finite are
+    /// ignored
+    pub fn set(&self, value: f64) {
+        if value.is_finite() {
+            self.var.set(self.spec.clamp(value));
+        }
+    }
+
+    pub fn get_01(&self) -> f64 {
+        self.spec.normalize(self.get())
+    }
+
+    /// Values which aren't finite are ignored
+    pub fn set_01(&self, value_01: f64) {
+        if value_01.is_finite() {
+            self.var.set(self.spec.denormalize(value_01));
+        }
+    }
+
+    pub fn reset(&self) {
+        self.set(self.spec.default);
+    }
+}
+
+/// Collection of named parameters in the order they were registered
+#[derive(Default)]
+pub struct ParamRegistry {
+    params: Vec<Param>,
+    index_by_name: HashMap<String, usize>,
+}
+
+impl ParamRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers an existing variable as a parameter and sets it to the parameter's default
+    /// value. Panics if a parameter with the same name is already registered.
+    pub fn register_var(&mut self, name: impl Into<String>, var: Var<f64>, spec: ParamSpec) {
+        let name = name.into();
+        assert!(
+            !self.index_by_name.contains_key(&name),
+            "parameter \"{}\" is already registered",
+            name
+        );
+        let param = Param { name, spec, var };
+        param.reset();
+        self.index_by_name
+            .insert(param.name.clone(), self.params.len());
+        self.params.push(param);
+    }
+
+    /// Registers a new parameter, returning a signal of its value which is smoothed according to
+    /// the parameter's spec
+    pub fn register(&mut self, name: impl Into<String>, spec: ParamSpec) -> Sf64 {
+        let var = Var::new(spec.default);
+        let signal = match spec.smoothing {
+            Some(smoothing) => var.smoothed_signal(smoothing),
+            None => var.buffered_signal(),
+        };
+        self.register_var(name, var, spec);
+        signal
+    }
+
+    pub fn param(&self, name: &str) -> Option<&Param> {
+        self.index_by_name.get(name).map(|&i| &self.params[i])
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Param> {
+        self.params.iter()
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.params.iter().map(Param::name)
+    }
+
+    pub fn len(&self) -> usize {
+        self.params.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.params.is_empty()
+    }
+
+    pub fn get(&self, name: &str) -> Option<f64> {
+        self.param(name).map(Param::get)
+    }
+
+    /// Returns false if there is no parameter with the given name
+    pub fn set(&self, name: &str, value: f64) -> bool {
+        self.param(name).map(|param| param.set(value)).is_some()
+    }
+
+    pub fn get_01(&self, name: &str) -> Option<f64> {
+        self.param(name).map(Param::get_01)
+    }
+
+    /// Returns false if there is no parameter with the given name
+    pub fn set_01(&self, name: &str, value_01: f64) -> bool {
+        self.param(name)
+            .map(|param| param.set_01(value_01))
+            .is_some()
+    }
+
+    pub fn reset_all(&self) {
+        self.params.iter().for_each(Param::reset);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-9, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn linear_round_trip() {
+        let spec = ParamSpec::new(-10.0, 30.0, 0.0);
+        assert_close(spec.normalize(-10.0), 0.0);
+        assert_close(spec.normalize(10.0), 0.5);
+        assert_close(spec.denormalize(0.75), 20.0);
+        for i in 0..=10 {
+            let value_01 = i as f64 / 10.0;
+            assert_close(spec.normalize(spec.denormalize(value_01)), value_01);
+        }
+    }
+
+    #[test]
+    fn exponential_round_trip() {
+        let spec = ParamSpec::new(20.0, 20000.0, 1000.0).with_curve(Curve::Exponential(4.0));
+        assert_close(spec.denormalize(0.0), 20.0);
+        assert_close(spec.denormalize(1.0), 20000.0);
+        // more of the normalized range is allocated to the low end
+        assert!(spec.denormalize(0.5) < 10010.0);
+        for i in 0..=10 {
+            let value_01 = i as f64 / 10.0;
+            assert_close(spec.normalize(spec.denormalize(value_01)), value_01);
+        }
+        let exp01 = Exp01::new(4.0);
+        for i in 0..=10 {
+            let x = i as f64 / 10.0;
+            assert_close(exp01.inverse(exp01.get(x)), x);
+        }
+    }
+
+    #[test]
+    fn clamp() {
+        let spec = ParamSpec::new(0.0, 10.0, 5.0);
+        assert_eq!(spec.clamp(-1.0), 0.0);
+        assert_eq!(spec.clamp(11.0), 10.0);
+        assert_eq!(spec.normalize(20.0), 1.0);
+        assert_eq!(spec.denormalize(-0.5), 0.0);
+        assert_eq!(spec.denormalize(1.5), 10.0);
+    }
+
+    #[test]
+    fn clamp_inverted_range() {
+        let spec = ParamSpec::new(10.0, 0.0, 5.0);
+        assert_eq!(spec.clamp(-1.0), 0.0);
+        assert_eq!(spec.clamp(11.0), 10.0);
+        assert_close(spec.normalize(10.0), 0.0);
+        assert_close(spec.normalize(0.0), 1.0);
+        assert_close(spec.denormalize(0.25), 7.5);
+    }
+
+    #[test]
+    fn normalize_zero_range() {
+        let spec = ParamSpec::new(3.0, 3.0, 3.0);
+        assert_eq!(spec.normalize(3.0), 0.0);
+        assert_eq!(spec.normalize(100.0), 0.0);
+        assert_eq!(spec.denormalize(0.5), 3.0);
+    }
+
+    #[test]
+    fn set_clamps_and_ignores_non_finite_values() {
+        let mut params = ParamRegistry::new();
+        params.register("p", ParamSpec::new(0.0, 10.0, 5.0));
+        assert!(params.set("p", 20.0));
+        assert_eq!(params.get("p"), Some(10.0));
+        for value in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            params.set("p", value);
+            params.set_01("p", value);
+            assert_eq!(params.get("p"), Some(10.0));
+        }
+        assert!(!params.set("q", 1.0));
+        params.reset_all();
+        assert_eq!(params.get("p"), Some(5.0));
+    }
+
+    #[test]
+    fn register_var_resets_to_default() {
+        let mut params = ParamRegistry::new();
+        let var = Var::new(1.0);
+        params.register_var("p", var.clone_ref(), ParamSpec::new(0.0, 10.0, 5.0));
+        assert_eq!(var.get(), 5.0);
+        assert_eq!(params.names().collect::<Vec<_>>(), vec!["p"]);
+    }
+
+    #[test]
+    #[should_panic(expected = "parameter \"p\" is already registered")]
+    fn register_duplicate_name() {
+        let mut params = ParamRegistry::new();
+        params.register("p", ParamSpec::new(0.0, 1.0, 0.0));
+        params.register("p", ParamSpec::new(0.0, 1.0, 0.0));
+    }
+}
@@ -0,0 +1,663 @@
+//! Compact notation for describing a cycle of events as a string.
+//!
+//! - words such as `bd` or `c2` are events, and the steps of a sequence evenly divide the cycle
+//! - `~` is a rest
+//! - `[a b]` plays a sequence in the time of a single step
+//! - `a, b` plays sequences at the same time (at the top level or inside `[]`)
+//! - `<a b>` plays one of its steps in each cycle, in turn
+//! - `a*n` plays a step `n` times in the time of a single step
+//! - `a!n` repeats a step `n` times, taking up `n` steps
+use std::{cmp::Ordering, error, fmt, ops, rc::Rc};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatternError {
+    /// byte offset into the pattern string where the error was found
+    pub position: usize,
+    pub message: String,
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (at position {})", self.message, self.position)
+    }
+}
+
+impl error::Error for PatternError {}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    gcd_u128(a as u128, b as u128) as u64
+}
+
+fn gcd_u128(a: u128, b: u128) -> u128 {
+    if b == 0 {
+        a
+    } else {
+        gcd_u128(b, a % b)
+    }
+}
+
+fn lcm(a: u64, b: u64) -> u64 {
+    (a / gcd(a, b)) * b
+}
+
+fn checked_lcm(a: u64, b: u64) -> Option<u64> {
+    (a / gcd(a, b)).checked_mul(b)
+}
+
+// Limit on the number of steps in the grid of a pattern (see `Pattern::grid`). This also keeps the
+// denominators of event times small enough that arithmetic on them can't overflow.
+const MAX_GRID_STEPS: u64 = 1 << 16;
+
+/// Non-negative fraction used to represent times within a pattern exactly. Intermediate values are
+/// computed with 128-bit integers, so arithmetic only overflows if its reduced result doesn't fit
+/// in 64 bits, which can't happen for the times of events of a parsed pattern.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rational {
+    numerator: u64,
+    denominator: u64,
+}
+
+impl Rational {
+    pub fn new(numerator: u64, denominator: u64) -> Self {
+        Self::new_u128(numerator as u128, denominator as u128)
+    }
+
+    fn new_u128(numerator: u128, denominator: u128) -> Self {
+        assert!(denominator != 0, "denominator of zero");
+        let divisor = gcd_u128(numerator, denominator).max(1);
+        let reduced = |x: u128| u64::try_from(x / divisor).expect("rational overflow");
+        Self {
+            numerator: reduced(numerator),
+            denominator: reduced(denominator),
+        }
+    }
+
+    pub fn integer(value: u64) -> Self {
+        Self::new(value, 1)
+    }
+
+    pub fn numerator(self) -> u64 {
+        self.numerator
+    }
+
+    pub fn denominator(self) -> u64 {
+        self.denominator
+    }
+
+    pub fn f64(self) -> f64 {
+        self.numerator as f64 / self.denominator as f64
+    }
+}
+
+impl ops::Add for Rational {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self {
+        Self::new_u128(
+            (self.numerator as u128 * rhs.denominator as u128)
+                + (rhs.numerator as u128 * self.denominator as u128),
+            self.denominator as u128 * rhs.denominator as u128,
+        )
+    }
+}
+
+impl ops::Mul for Rational {
+    type Output = Self;
+    fn mul(self, rhs: Self) -> Self {
+        Self::new_u128(
+            self.numerator as u128 * rhs.numerator as u128,
+            self.denominator as u128 * rhs.denominator as u128,
+        )
+    }
+}
+
+impl PartialOrd for Rational {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Rational {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.numerator as u128 * other.denominator as u128)
+            .cmp(&(other.numerator as u128 * self.denominator as u128))
+    }
+}
+
+/// A single event within a cycle, where times are expressed as fractions of a cycle
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    pub start: Rational,
+    pub duration: Rational,
+    pub value: String,
+    /// byte offset into the pattern string of the word which produced the event
+    pub position: usize,
+}
+
+#[derive(Debug, Clone)]
+enum Term {
+    Word { value: String, position: usize },
+    Rest,
+    Stack(Vec<Vec<Step>>),
+    Alternation(Vec<Step>),
+}
+
+#[derive(Debug, Clone)]
+struct Step {
+    // shared so that replicating a step doesn't copy its term
+    term: Rc<Term>,
+    fast: u64,
+}
+
+impl Term {
+    // Number of cycles after which the events repeat, or `None` if it overflows
+    fn period_cycles(&self) -> Option<u64> {
+        match self {
+            Self::Word { .. } | Self::Rest => Some(1),
+            Self::Stack(sequences) => sequences
+                .iter()
+                .flatten()
+                .try_fold(1, |acc, step| checked_lcm(acc, step.period_cycles()?)),
+            Self::Alternation(steps) => steps
+                .iter()
+                .try_fold(1, |acc, step| checked_lcm(acc, step.period_cycles()?))?
+                .checked_mul(steps.len() as u64),
+        }
+    }
+
+    // Number of equal parts of a cycle such that every event starts and ends on a part, or `None`
+    // if it overflows
+    fn resolution(&self) -> Option<u64> {
+        match self {
+            Self::Word { .. } | Self::Rest => Some(1),
+            Self::Stack(sequences) => sequences.iter().try_fold(1, |acc, sequence| {
+                let resolution = sequence
+                    .iter()
+                    .try_fold(1, |acc, step| checked_lcm(acc, step.resolution()?))?;
+                checked_lcm(acc, resolution.checked_mul(sequence.len() as u64)?)
+            }),
+            Self::Alternation(steps) => steps
+                .iter()
+                .try_fold(1, |acc, step| checked_lcm(acc, step.resolution()?)),
+        }
+    }
+
+    // Upper bound on the number of steps in the grid of the term, or `None` if it overflows
+    fn grid_steps(&self) -> Option<u64> {
+        self.resolution()?.checked_mul(self.period_cycles()?)
+    }
+
+    fn events(&self, cycle: u64, start: Rational, duration: Rational, events: &mut Vec<Event>) {
+        match self {
+            Self::Word { value, position } => events.push(Event {
+                start,
+                duration,
+                value: value.clone(),
+                position: *position,
+            }),
+            Self::Rest => (),
+            Self::Stack(sequences) => {
+                for sequence in sequences {
+                    let step_duration = duration * Rational::new(1, sequence.len() as u64);
+                    for (i, step) in sequence.iter().enumerate() {
+                        let step_start = start + (step_duration * Rational::integer(i as u64));
+                        step.events(cycle, step_start, step_duration, events);
+                    }
+                }
+            }
+            Self::Alternation(steps) => {
+                let len = steps.len() as u64;
+                steps[(cycle % len) as usize].events(cycle / len, start, duration, events);
+            }
+        }
+    }
+}
+
+impl Step {
+    fn period_cycles(&self) -> Option<u64> {
+        let period = self.term.period_cycles()?;
+        Some(period / gcd(period, self.fast))
+    }
+
+    fn resolution(&self) -> Option<u64> {
+        self.term.resolution()?.checked_mul(self.fast)
+    }
+
+    fn grid_steps(&self) -> Option<u64> {
+        self.resolution()?.checked_mul(self.period_cycles()?)
+    }
+
+    fn events(&self, cycle: u64, start: Rational, duration: Rational, events: &mut Vec<Event>) {
+        let repeat_duration = duration * Rational::new(1, self.fast);
+        for i in 0..self.fast {
+            let repeat_start = start + (repeat_duration * Rational::integer(i));
+            self.term.events(
+                (cycle * self.fast) + i,
+                repeat_start,
+                repeat_duration,
+                events,
+            );
+        }
+    }
+}
+
+struct Parser {
+    chars: Vec<(usize, char)>,
+    index: usize,
+    len: usize,
+}
+
+fn is_word_char(ch: char) -> bool {
+    ch.is_alphanumeric() || matches!(ch, '#' | '.' | '-' | '_' | ':')
+}
+
+impl Parser {
+    fn new(source: &str) -> Self {
+        Self {
+            chars: source.char_indices().collect(),
+            index: 0,
+            len: source.len(),
+        }
+    }
+
+    fn position(&self) -> usize {
+        self.chars.get(self.index).map_or(self.len, |&(i, _)| i)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.index).map(|&(_, ch)| ch)
+    }
+
+    fn error<T>(&self, message: impl Into<String>) -> Result<T, PatternError> {
+        Err(PatternError {
+            position: self.position(),
+            message: message.into(),
+        })
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.index += 1;
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), PatternError> {
+        self.skip_whitespace();
+        if self.peek() == Some(expected) {
+            self.index += 1;
+            Ok(())
+        } else {
+            self.error(format!("expected '{}'", expected))
+        }
+    }
+
+    // Parses sequences separated by ',' up to (but not including) the `end` character
+    fn stack(&mut self, end: Option<char>) -> Result<Vec<Vec<Step>>, PatternError> {
+        let mut sequences = vec![self.sequence(end)?];
+        loop {
+            self.skip_whitespace();
+            if self.peek() != Some(',') {
+                break;
+            }
+            self.index += 1;
+            sequences.push(self.sequence(end)?);
+        }
+        Ok(sequences)
+    }
+
+    fn sequence(&mut self, end: Option<char>) -> Result<Vec<Step>, PatternError> {
+        let mut steps = Vec::new();
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                None | Some(',') => break,
+                Some(ch) if Some(ch) == end => break,
+                Some(_) => self.step(&mut steps)?,
+            }
+        }
+        if steps.is_empty() {
+            self.error("expected a step")
+        } else {
+            Ok(steps)
+        }
+    }
+
+    fn number(&mut self) -> Result<u64, PatternError> {
+        let start = self.index;
+        while self.peek().is_some_and(|ch| ch.is_ascii_digit()) {
+            self.index += 1;
+        }
+        let digits = self.chars[start..self.index]
+            .iter()
+            .map(|&(_, ch)| ch)
+            .collect::<String>();
+        match digits.parse::<u64>() {
+            Ok(0) => {
+                self.index = start;
+                self.error("expected a number greater than 0")
+            }
+            Ok(number) => Ok(number),
+            Err(_) => {
+                self.index = start;
+                self.error("expected a number")
+            }
+        }
+    }
+
+    // Parses a single step along with its modifiers, adding it to `steps` once for each time it's
+    // repeated with '!'
+    fn step(&mut self, steps: &mut Vec<Step>) -> Result<(), PatternError> {
+        let position = self.position();
+        let term = self.term()?;
+        let mut fast = 1_u64;
+        let mut replicate = 1_u64;
+        loop {
+            match self.peek() {
+                Some('*') => {
+                    self.index += 1;
+                    let position = self.position();
+                    fast = match fast.checked_mul(self.number()?) {
+                        Some(fast) if fast <= MAX_GRID_STEPS => fast,
+                        _ => return self.too_many_steps(position),
+                    };
+                }
+                Some('!') => {
+                    self.index += 1;
+                    let position = self.position();
+                    replicate = match replicate.checked_mul(self.number()?) {
+                        Some(replicate) if steps.len() as u64 + replicate <= MAX_GRID_STEPS => {
+                            replicate
+                        }
+                        _ => return self.too_many_steps(position),
+                    };
+                }
+                _ => break,
+            }
+        }
+        let step = Step {
+            term: Rc::new(term),
+            fast,
+        };
+        if step
+            .grid_steps()
+            .is_none_or(|grid_steps| grid_steps > MAX_GRID_STEPS)
+        {
+            return self.too_many_steps(position);
+        }
+        for _ in 0..replicate {
+            steps.push(step.clone());
+        }
+        Ok(())
+    }
+
+    fn too_many_steps<T>(&self, position: usize) -> Result<T, PatternError> {
+        Err(PatternError {
+            position,
+            message: format!(
+                "pattern has too many steps (at most {} are allowed)",
+                MAX_GRID_STEPS
+            ),
+        })
+    }
+
+    fn term(&mut self) -> Result<Term, PatternError> {
+        match self.peek() {
+            Some('~') => {
+                self.index += 1;
+                Ok(Term::Rest)
+            }
+            Some('[') => {
+                self.index += 1;
+                let sequences = self.stack(Some(']'))?;
+                self.expect(']')?;
+                Ok(Term::Stack(sequences))
+            }
+            Some('<') => {
+                self.index += 1;
+                let steps = self.sequence(Some('>'))?;
+                self.expect('>')?;
+                Ok(Term::Alternation(steps))
+            }
+            Some(ch) if is_word_char(ch) => {
+                let position = self.position();
+                let mut value = String::new();
+                while let Some(ch) = self.peek().filter(|&ch| is_word_char(ch)) {
+                    value.push(ch);
+                    self.index += 1;
+                }
+                Ok(Term::Word { value, position })
+            }
+            Some(ch) => self.error(format!("unexpected '{}'", ch)),
+            None => self.error("unexpected end of pattern"),
+        }
+    }
+}
+
+/// A parsed pattern which produces events for each cycle
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    term: Term,
+    period_cycles: u64,
+}
+
+impl Pattern {
+    pub fn parse(source: &str) -> Result<Self, PatternError> {
+        let mut parser = Parser::new(source);
+        let sequences = parser.stack(None)?;
+        parser.skip_whitespace();
+        if let Some(ch) = parser.peek() {
+            return parser.error(format!("unexpected '{}'", ch));
+        }
+        let term = Term::Stack(sequences);
+        match (term.grid_steps(), term.period_cycles()) {
+            (Some(grid_steps), Some(period_cycles)) if grid_steps <= MAX_GRID_STEPS => Ok(Self {
+                term,
+                period_cycles,
+            }),
+            _ => parser.too_many_steps(0),
+        }
+    }
+
+    /// Events in the given cycle sorted by their start time
+    pub fn events(&self, cycle: u64) -> Vec<Event> {
+        let mut events = Vec::new();
+        self.term.events(
+            cycle,
+            Rational::integer(0),
+            Rational::integer(1),
+            &mut events,
+        );
+        events.sort_by_key(|event| event.start);
+        events
+    }
+
+    /// Number of cycles after which the events repeat
+    pub fn period_cycles(&self) -> u64 {
+        self.period_cycles
+    }
+
+    /// Smallest number of evenly-spaced steps per cycle such that every event starts on a step
+    pub fn steps_per_cycle(&self) -> u64 {
+        (0..self.period_cycles())
+            .flat_map(|cycle| self.events(cycle))
+            .map(|event| event.start.denominator())
+            .fold(1, lcm)
+    }
+
+    /// The events of every cycle of the pattern (until it repeats) arranged into a grid with
+    /// `steps_per_cycle` steps per cycle. Each step contains the events which start on that step.
+    pub fn grid(&self) -> Vec<Vec<Event>> {
+        let steps_per_cycle = self.steps_per_cycle();
+        let mut grid = Vec::new();
+        for cycle in 0..self.period_cycles() {
+            let mut steps = vec![Vec::new(); steps_per_cycle as usize];
+            for event in self.events(cycle) {
+                let start = event.start * Rational::integer(steps_per_cycle);
+                steps[start.numerator() as usize].push(event);
+            }
+            grid.extend(steps);
+        }
+        grid
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn r(numerator: u64, denominator: u64) -> Rational {
+        Rational::new(numerator, denominator)
+    }
+
+    // The value, start and duration of each event in a cycle of a pattern
+    fn events(source: &str, cycle: u64) -> Vec<(String, Rational, Rational)> {
+        Pattern::parse(source)
+            .unwrap()
+            .events(cycle)
+            .into_iter()
+            .map(|event| (event.value, event.start, event.duration))
+            .collect()
+    }
+
+    fn e(value: &str, start: Rational, duration: Rational) -> (String, Rational, Rational) {
+        (value.to_string(), start, duration)
+    }
+
+    fn error_position(source: &str) -> usize {
+        Pattern::parse(source).unwrap_err().position
+    }
+
+    #[test]
+    fn sequence() {
+        assert_eq!(
+            events("a b ~ c", 0),
+            vec![
+                e("a", r(0, 1), r(1, 4)),
+                e("b", r(1, 4), r(1, 4)),
+                e("c", r(3, 4), r(1, 4)),
+            ]
+        );
+    }
+
+    #[test]
+    fn subdivision() {
+        assert_eq!(
+            events("[a b] c", 0),
+            vec![
+                e("a", r(0, 1), r(1, 4)),
+                e("b", r(1, 4), r(1, 4)),
+                e("c", r(1, 2), r(1, 2)),
+            ]
+        );
+    }
+
+    #[test]
+    fn fast() {
+        assert_eq!(
+            events("a*3 b", 0),
+            vec![
+                e("a", r(0, 1), r(1, 6)),
+                e("a", r(1, 6), r(1, 6)),
+                e("a", r(1, 3), r(1, 6)),
+                e("b", r(1, 2), r(1, 2)),
+            ]
+        );
+    }
+
+    #[test]
+    fn replicate() {
+        assert_eq!(
+            events("a!2 b", 0),
+            vec![
+                e("a", r(0, 1), r(1, 3)),
+                e("a", r(1, 3), r(1, 3)),
+                e("b", r(2, 3), r(1, 3)),
+            ]
+        );
+    }
+
+    #[test]
+    fn alternation() {
+        let pattern = "<a b> c";
+        assert_eq!(
+            events(pattern, 0),
+            vec![e("a", r(0, 1), r(1, 2)), e("c", r(1, 2), r(1, 2))]
+        );
+        assert_eq!(
+            events(pattern, 1),
+            vec![e("b", r(0, 1), r(1, 2)), e("c", r(1, 2), r(1, 2))]
+        );
+        assert_eq!(events(pattern, 2), events(pattern, 0));
+    }
+
+    #[test]
+    fn stack() {
+        assert_eq!(
+            events("a, b c", 0),
+            vec![
+                e("a", r(0, 1), r(1, 1)),
+                e("b", r(0, 1), r(1, 2)),
+                e("c", r(1, 2), r(1, 2)),
+            ]
+        );
+    }
+
+    #[test]
+    fn alternation_fast() {
+        // each repetition of the alternation advances it by one step
+        let pattern = Pattern::parse("<a b c>*2 d").unwrap();
+        assert_eq!(pattern.period_cycles(), 3);
+        assert_eq!(pattern.steps_per_cycle(), 4);
+        let grid = pattern
+            .grid()
+            .into_iter()
+            .map(|step| {
+                step.into_iter()
+                    .map(|event| event.value)
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            grid,
+            vec!["a", "b", "d", "", "c", "a", "d", "", "b", "c", "d", ""]
+        );
+    }
+
+    #[test]
+    fn period_of_fast_alternation() {
+        let pattern = Pattern::parse("<a b>*2").unwrap();
+        assert_eq!(pattern.period_cycles(), 1);
+        assert_eq!(pattern.steps_per_cycle(), 2);
+    }
+
+    #[test]
+    fn too_many_steps() {
+        assert_eq!(error_position("a*99999999999*99999999999"), 2);
+        assert_eq!(error_position("b a*99999999999"), 4);
+        assert_eq!(error_position("a!18446744073709551615"), 2);
+        assert_eq!(error_position("b a!65536"), 4);
+        assert_eq!(error_position("a!65536 b"), 0);
+        assert_eq!(error_position("a [[b*256]*257]"), 3);
+        // the limit applies to the whole pattern even if each step is within it
+        assert_eq!(error_position("[a*65536] b"), 0);
+        assert_eq!(error_position("<a!300> <b!301>"), 0);
+        assert_eq!(error_position("a*99999999999999999999999"), 2);
+        assert!(Pattern::parse("[a!256]!256").is_ok());
+    }
+
+    #[test]
+    fn large_denominators() {
+        assert_eq!(error_position("a*65521, b*65519"), 0);
+        let events = events("[a*127, b*113] c", 0);
+        assert_eq!(events.len(), 127 + 113 + 1);
+        assert_eq!(events.last().unwrap(), &e("c", r(1, 2), r(1, 2)));
+    }
+
+    #[test]
+    fn error_positions() {
+        assert_eq!(error_position("a [b"), 4);
+        assert_eq!(error_position("a*0"), 2);
+        assert_eq!(error_position("a )"), 2);
+        assert_eq!(error_position(""), 0);
+    }
+}
@@ -0,0 +1,324 @@
+use hound::{SampleFormat, WavReader};
+use std::{
+    collections::BTreeMap,
+    error, fmt, fs,
+    io::{self, BufReader, Read},
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+#[derive(Debug)]
+pub enum SampleLoadError {
+    Io(io::Error),
+    Wav(hound::Error),
+    UnsupportedFormat {
+        float: bool,
+        bits_per_sample: u16,
+    },
+    /// Error loading a particular file while loading a directory of samples
+    File {
+        path: PathBuf,
+        error: Box<SampleLoadError>,
+    },
+}
+
+impl fmt::Display for SampleLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "io error: {}", error),
+            Self::Wav(error) => write!(f, "wav error: {}", error),
+            Self::UnsupportedFormat {
+                float,
+                bits_per_sample,
+            } => write!(
+                f,
+                "unsupported sample format: {}-bit {}",
+                bits_per_sample,
+                if *float { "float" } else { "int" }
+            ),
+            Self::File { path, error } => write!(f, "{}: {}", path.display(), error),
+        }
+    }
+}
+
+impl error::Error for SampleLoadError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::Io(error) => Some(error),
+            Self::Wav(error) => Some(error),
+            Self::UnsupportedFormat { .. } => None,
+            Self::File { error, .. } => Some(error.as_ref()),
+        }
+    }
+}
+
+impl From<io::Error> for SampleLoadError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<hound::Error> for SampleLoadError {
+    fn from(error: hound::Error) -> Self {
+        Self::Wav(error)
+    }
+}
+
+// Reads all the samples in a wav file, scaling them to the range -1 to 1 and mixing all the
+// channels of each frame together.
+fn read_wav_mono<R: Read>(mut reader: WavReader<R>) -> Result<Sample, SampleLoadError> {
+    let spec = reader.spec();
+    let data = match (spec.sample_format, spec.bits_per_sample) {
+        (SampleFormat::Float, 32) => reader
+            .samples::<f32>()
+            .map(|x| x.map(|x| x as f64))
+            .collect::<Result<Vec<_>, _>>()?,
+        // 8-bit wav data is unsigned but hound converts it to signed values when reading
+        (SampleFormat::Int, bits_per_sample @ (8 | 16 | 24 | 32)) => {
+            let max_value = (1_u64 << (bits_per_sample - 1)) as f64;
+            reader
+                .samples::<i32>()
+                .map(|x| x.map(|x| x as f64 / max_value))
+                .collect::<Result<Vec<_>, _>>()?
+        }
+        (sample_format, bits_per_sample) => {
+            return Err(SampleLoadError::UnsupportedFormat {
+                float: sample_format == SampleFormat::Float,
+                bits_per_sample,
+            })
+        }
+    };
+    let data = data
+        .chunks(spec.channels as usize)
+        .map(|frame| (frame.iter().sum::<f64>() / frame.len() as f64) as f32)
+        .collect::<Vec<_>>();
+    Ok(Sample::new(data, spec.sample_rate))
+}
+
+/// Loads every file with a ".wav" extension in a directory, keyed by the file's name without the
+/// extension
+pub fn load_wav_dir<P: AsRef<Path>>(path: P) -> Result<BTreeMap<String, Sample>, SampleLoadError> {
+    let mut samples = BTreeMap::new();
+    for entry in fs::read_dir(path)? {
+        let path = entry?.path();
+        let is_wav = path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("wav"));
+        if !is_wav || !path.is_file() {
+            continue;
+        }
+        let name = match path.file_stem() {
+            Some(name) => name.to_string_lossy().to_string(),
+            None => continue,
+        };
+        match Sample::from_wav_file(&path) {
+            Ok(sample) => {
+                samples.insert(name, sample);
+            }
+            Err(error) => {
+                return Err(SampleLoadError::File {
+                    path,
+                    error: Box::new(error),
+                })
+            }
+        }
+    }
+    Ok(samples)
+}
+
+/// Monophonic audio data along with the sample rate it was recorded at. Cloning a `Sample` shares
+/// the underlying data.
+#[derive(Clone)]
+pub struct Sample {
+    data: Rc<[f32]>,
+    sample_rate_hz: u32,
+}
+
+impl Sample {
+    pub fn new(data: Vec<f32>, sample_rate_hz: u32) -> Self {
+        Self {
+            data: data.into(),
+            sample_rate_hz,
+        }
+    }
+
+    /// Loads a wav file with 8, 16, 24 or 32-bit integer samples or 32-bit float samples. Files
+    /// with multiple channels are mixed down to a single channel.
+    pub fn from_wav<R: Read>(reader: R) -> Result<Self, SampleLoadError> {
+        read_wav_mono(WavReader::new(reader)?)
+    }
+
+    pub fn from_wav_bytes(bytes: &[u8]) -> Result<Self, SampleLoadError> {
+        Self::from_wav(bytes)
+    }
+
+    pub fn from_wav_file<P: AsRef<Path>>(path: P) -> Result<Self, SampleLoadError> {
+        Self::from_wav(BufReader::new(fs::File::open(path)?))
+    }
+
+    pub fn data(&self) -> &[f32] {
+        &self.data
+    }
+
+    pub fn sample_rate_hz(&self) -> u32 {
+        self.sample_rate_hz
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn duration_seconds(&self) -> f64 {
+        self.data.len() as f64 / self.sample_rate_hz as f64
+    }
+
+    /// Indices of frames where the level of the sample rises by at least `rise_db` decibels
+    /// compared to the moment before. The first frame is always included.
+    pub fn detect_transients(&self, rise_db: f64) -> Vec<usize> {
+        // Levels are measured over blocks of this duration
+        const BLOCK_SECONDS: f64 = 0.005;
+        // Blocks quieter than this are never considered the start of a transient
+        const MIN_LEVEL_DB: f64 = -60.0;
+        // Transients closer together than this are treated as a single transient
+        const MIN_GAP_SECONDS: f64 = 0.05;
+        let block_len = ((BLOCK_SECONDS * self.sample_rate_hz as f64) as usize).max(1);
+        let min_gap = (MIN_GAP_SECONDS * self.sample_rate_hz as f64) as usize;
+        let mut transients = vec![0];
+        let mut prev_level_db = MIN_LEVEL_DB;
+        for (i, block) in self.data.chunks(block_len).enumerate() {
+            let mean_square =
+                block.iter().map(|&x| (x as f64) * (x as f64)).sum::<f64>() / block.len() as f64;
+            let level_db = (10.0 * mean_square.log10()).max(MIN_LEVEL_DB);
+            let index = i * block_len;
+            let last = transients[transients.len() - 1];
+            if level_db > MIN_LEVEL_DB
+                && level_db - prev_level_db >= rise_db
+                && index >= last + min_gap
+            {
+                transients.push(index);
+            }
+            prev_level_db = level_db;
+        }
+        transients
+    }
+
+    fn get(&self, index: isize) -> f64 {
+        if index < 0 {
+            0.0
+        } else {
+            self.data.get(index as usize).map_or(0.0, |&x| x as f64)
+        }
+    }
+
+    /// Value of the sample at a fractional index computed with cubic hermite interpolation.
+    /// Positions outside the sample are treated as silence.
+    pub fn interpolate(&self, position: f64) -> f64 {
+        let index = position.floor();
+        let t = position - index;
+        let index = index as isize;
+        let xm1 = self.get(index - 1);
+        let x0 = self.get(index);
+        let x1 = self.get(index + 1);
+        let x2 = self.get(index + 2);
+        let c1 = 0.5 * (x1 - xm1);
+        let c2 = xm1 - (2.5 * x0) + (2.0 * x1) - (0.5 * x2);
+        let c3 = (0.5 * (x2 - xm1)) + (1.5 * (x0 - x1));
+        (((((c3 * t) + c2) * t) + c1) * t) + x0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use hound::{WavSpec, WavWriter};
+    use std::io::Cursor;
+
+    fn wav_bytes<S: hound::Sample + Copy>(spec: WavSpec, samples: &[S]) -> Vec<u8> {
+        let mut cursor = Cursor::new(Vec::new());
+        let mut writer = WavWriter::new(&mut cursor, spec).unwrap();
+        for &sample in samples {
+            writer.write_sample(sample).unwrap();
+        }
+        writer.finalize().unwrap();
+        cursor.into_inner()
+    }
+
+    fn spec(channels: u16, bits_per_sample: u16, sample_format: SampleFormat) -> WavSpec {
+        WavSpec {
+            channels,
+            sample_rate: 8000,
+            bits_per_sample,
+            sample_format,
+        }
+    }
+
+    #[test]
+    fn int_formats() {
+        for bits_per_sample in [8, 16, 24, 32] {
+            let max_value = 1_i64 << (bits_per_sample - 1);
+            let samples = [0, max_value / 2, -max_value / 4, -max_value].map(|x| x as i32);
+            let bytes = wav_bytes(spec(1, bits_per_sample, SampleFormat::Int), &samples);
+            let sample = Sample::from_wav_bytes(&bytes).unwrap();
+            assert_eq!(sample.data(), &[0.0, 0.5, -0.25, -1.0], "{bits_per_sample}");
+            assert_eq!(sample.sample_rate_hz(), 8000);
+        }
+    }
+
+    #[test]
+    fn float_format() {
+        let samples = [0.0_f32, 0.75, -1.0];
+        let bytes = wav_bytes(spec(1, 32, SampleFormat::Float), &samples);
+        let sample = Sample::from_wav_bytes(&bytes).unwrap();
+        assert_eq!(sample.data(), &samples);
+    }
+
+    #[test]
+    fn channels_are_mixed() {
+        let bytes = wav_bytes(
+            spec(2, 16, SampleFormat::Int),
+            &[16384_i16, 0, -32768, 16384],
+        );
+        let sample = Sample::from_wav_bytes(&bytes).unwrap();
+        assert_eq!(sample.data(), &[0.25, -0.25]);
+        assert_eq!(sample.duration_seconds(), 2.0 / 8000.0);
+    }
+
+    #[test]
+    fn unsupported_format() {
+        // a header describing a single 40-bit integer sample, which hound can't write
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"RIFF");
+        bytes.extend_from_slice(&(4 + 24 + 8 + 5_u32).to_le_bytes());
+        bytes.extend_from_slice(b"WAVEfmt ");
+        bytes.extend_from_slice(&16_u32.to_le_bytes());
+        bytes.extend_from_slice(&1_u16.to_le_bytes()); // PCM
+        bytes.extend_from_slice(&1_u16.to_le_bytes()); // channels
+        bytes.extend_from_slice(&8000_u32.to_le_bytes()); // sample rate
+        bytes.extend_from_slice(&(8000 * 5_u32).to_le_bytes()); // bytes per second
+        bytes.extend_from_slice(&5_u16.to_le_bytes()); // block align
+        bytes.extend_from_slice(&40_u16.to_le_bytes()); // bits per sample
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&5_u32.to_le_bytes());
+        bytes.extend_from_slice(&[0; 5]);
+        match Sample::from_wav_bytes(&bytes) {
+            Err(SampleLoadError::UnsupportedFormat {
+                float: false,
+                bits_per_sample: 40,
+            }) => (),
+            Err(error) => panic!("unexpected error: {error}"),
+            Ok(_) => panic!("unexpected success"),
+        }
+    }
+
+    #[test]
+    fn invalid_wav() {
+        assert!(matches!(
+            Sample::from_wav_bytes(b"not a wav file"),
+            Err(SampleLoadError::Wav(_))
+        ));
+    }
+}
@@ -157,6 +157,9 @@ impl Su8 {
             self.map(|x| x & (1 << 7) != 0),
         ]
     }
+    pub fn f64(&self) -> Sf64 {
+        self.map(|x| x as f64)
+    }
 }
 
 struct Debug<T: Clone + 'static, F: FnMut(T, &SignalCtx)> {
@@ -220,12 +223,83 @@ impl Var<bool> {
     }
 }
 
+impl Var<f64> {
+    /// Signal which ramps to each new value of the variable rather than jumping to it, to avoid
+    /// clicks when the variable is changed
+    pub fn smoothed_signal(&self, smoothing: Smoothing) -> Sf64 {
+        BufferedSignal::new(SmoothedVar {
+            var: self.clone_ref(),
+            smoothing,
+            value: None,
+            ramp_target: 0.0,
+            ramp_step: 0.0,
+        })
+    }
+}
+
 impl<T: Clone + 'static> SignalTrait<T> for Var<T> {
     fn sample(&mut self, _ctx: &SignalCtx) -> T {
         self.get()
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Smoothing {
+    /// Ramps at a constant rate, reaching each new value after `seconds`
+    Linear { seconds: f64 },
+    /// Moves a constant fraction of the remaining distance to the new value each sample, where
+    /// `seconds` is the time taken to move 63% of the way
+    Exponential { seconds: f64 },
+}
+
+struct SmoothedVar {
+    var: Var<f64>,
+    smoothing: Smoothing,
+    value: Option<f64>,
+    ramp_target: f64,
+    ramp_step: f64,
+}
+
+impl SignalTrait<f64> for SmoothedVar {
+    fn sample(&mut self, ctx: &SignalCtx) -> f64 {
+        let target = self.var.get();
+        // start at the initial value of the variable rather than ramping to it
+        let value = match self.value {
+            None => {
+                self.ramp_target = target;
+                target
+            }
+            Some(value) => match self.smoothing {
+                Smoothing::Linear { seconds } => {
+                    if target != self.ramp_target {
+                        let ramp_samples = (seconds * ctx.sample_rate as f64).max(1.0);
+                        self.ramp_target = target;
+                        self.ramp_step = (target - value) / ramp_samples;
+                    }
+                    let next = value + self.ramp_step;
+                    if (self.ramp_step >= 0.0 && next >= target)
+                        || (self.ramp_step <= 0.0 && next <= target)
+                    {
+                        target
+                    } else {
+                        next
+                    }
+                }
+                Smoothing::Exponential { seconds } => {
+                    let time_constant_samples = seconds * ctx.sample_rate as f64;
+                    if time_constant_samples <= 1.0 {
+                        target
+                    } else {
+                        value + ((target - value) * (1.0 - (-1.0 / time_constant_samples).exp()))
+                    }
+                }
+            },
+        };
+        self.value = Some(value);
+        value
+    }
+}
+
 pub struct TriggerVar {
     var: Var<bool>,
 }
@@ -343,13 +417,14 @@ impl<T: Clone + 'static, U: Clone + 'static> SignalTrait<T> for Force<T, U> {
 // The k parameter controls how sharp the curve is.
 // It approaches a linear function as k approaches 0.
 // k = 0 is special cased as a linear function for convenience.
-struct Exp01 {
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Exp01 {
     k: f64,
     a: f64,
     b: f64,
 }
 impl Exp01 {
-    fn new(k: f64) -> Self {
+    pub(crate) fn new(k: f64) -> Self {
         if k == 0.0 {
             Self {
                 k: 0.0,
@@ -363,13 +438,22 @@ impl Exp01 {
         }
     }
 
-    fn get(&self, x: f64) -> f64 {
+    pub(crate) fn get(&self, x: f64) -> f64 {
         if self.k == 0.0 {
             x
         } else {
             (self.k * (x - self.a)).exp() - self.b
         }
     }
+
+    // The x such that f(x) = y
+    pub(crate) fn inverse(&self, y: f64) -> f64 {
+        if self.k == 0.0 {
+            y
+        } else {
+            self.a + ((y + self.b).ln() / self.k)
+        }
+    }
 }
 struct Exp01Signal {
     signal: Sf64,
@@ -121,6 +121,99 @@ pub mod weighted_sum {
     }
 }
 
+pub mod mod_matrix {
+    use crate::signal::*;
+    use std::{cell::RefCell, rc::Rc};
+
+    /// Adds a source to a destination, scaled by an amount
+    pub struct Slot {
+        pub source: usize,
+        pub destination: usize,
+        pub amount: Sf64,
+    }
+
+    pub struct Props {
+        pub sources: Vec<Sf64>,
+        /// value of each destination before any sources are added to it
+        pub destination_offsets: Vec<Sf64>,
+        pub slots: Vec<Slot>,
+    }
+
+    struct Signal {
+        props: Props,
+        source_samples: Vec<f64>,
+        // the destinations are written into a buffer shared with their signals rather than being
+        // returned so that nothing is allocated while sampling
+        destinations: Rc<RefCell<Vec<f64>>>,
+    }
+
+    impl SignalTrait<()> for Signal {
+        fn sample(&mut self, ctx: &SignalCtx) {
+            // every source is sampled on every sample so that stateful sources (e.g. LFOs) keep
+            // running while they aren't routed anywhere
+            for (sample, source) in self
+                .source_samples
+                .iter_mut()
+                .zip(self.props.sources.iter_mut())
+            {
+                *sample = source.sample(ctx);
+            }
+            let mut destinations = self.destinations.borrow_mut();
+            for (destination, offset) in destinations
+                .iter_mut()
+                .zip(self.props.destination_offsets.iter_mut())
+            {
+                *destination = offset.sample(ctx);
+            }
+            for slot in self.props.slots.iter_mut() {
+                destinations[slot.destination] +=
+                    self.source_samples[slot.source] * slot.amount.sample(ctx);
+            }
+        }
+    }
+
+    struct Destination {
+        matrix: BufferedSignal<()>,
+        destinations: Rc<RefCell<Vec<f64>>>,
+        index: usize,
+    }
+
+    impl SignalTrait<f64> for Destination {
+        fn sample(&mut self, ctx: &SignalCtx) -> f64 {
+            // the matrix is buffered so it's only updated once per sample
+            self.matrix.sample(ctx);
+            self.destinations.borrow()[self.index]
+        }
+    }
+
+    /// Returns a signal for each destination
+    pub fn create(props: Props) -> Vec<Sf64> {
+        for slot in &props.slots {
+            assert!(slot.source < props.sources.len(), "no such source");
+            assert!(
+                slot.destination < props.destination_offsets.len(),
+                "no such destination"
+            );
+        }
+        let num_destinations = props.destination_offsets.len();
+        let destinations = Rc::new(RefCell::new(vec![0.0; num_destinations]));
+        let matrix = BufferedSignal::new(Signal {
+            source_samples: vec![0.0; props.sources.len()],
+            destinations: Rc::clone(&destinations),
+            props,
+        });
+        (0..num_destinations)
+            .map(|index| {
+                BufferedSignal::new(Destination {
+                    matrix: matrix.clone_ref(),
+                    destinations: Rc::clone(&destinations),
+                    index,
+                })
+            })
+            .collect()
+    }
+}
+
 pub mod amplify {
     use crate::signal::*;
 
@@ -251,6 +344,8 @@ pub mod biquad_filter {
     // This is based on the filter designs at:
     // https://exstrom.com/journal/sigproc/dsigproc.html
 
+    use std::f64::consts::PI;
+
     #[derive(Default)]
     struct BufferEntry {
         a: f64,
@@ -311,6 +406,101 @@ pub mod biquad_filter {
         }
     }
 
+    fn update_butterworth_low_pass_entries(
+        buffer: &mut Buffer,
+        half_power_frequency_sample_rate_ratio: f64,
+    ) {
+        let a = (PI * half_power_frequency_sample_rate_ratio).tan();
+        let a2 = a * a;
+        let n = buffer.entries.len() as f64;
+        for (i, entry) in buffer.entries.iter_mut().enumerate() {
+            let r = ((PI * ((2.0 * i as f64) + 1.0)) / (4.0 * n)).sin();
+            let s = a2 + (2.0 * a * r) + 1.0;
+            entry.a = a2 / s;
+            entry.d1 = (2.0 * (1.0 - a2)) / s;
+            entry.d2 = -(a2 - (2.0 * a * r) + 1.0) / s;
+        }
+    }
+
+    /// Butterworth low pass filter with a fixed cutoff which is applied to individual samples
+    /// rather than to a signal, for use within other modules
+    pub(super) struct ButterworthLowPass {
+        buffer: Buffer,
+    }
+
+    impl ButterworthLowPass {
+        pub(super) fn new(
+            filter_order_half: usize,
+            half_power_frequency_sample_rate_ratio: f64,
+        ) -> Self {
+            let mut buffer = Buffer::new(filter_order_half);
+            update_butterworth_low_pass_entries(
+                &mut buffer,
+                half_power_frequency_sample_rate_ratio,
+            );
+            Self { buffer }
+        }
+
+        pub(super) fn apply(&mut self, sample: f64) -> f64 {
+            self.buffer.apply_low_pass(sample)
+        }
+    }
+
+    /// Single second order filter whose coefficients are calculated from a frequency and Q,
+    /// applied to individual samples rather than to a signal, for use within other modules. This
+    /// is based on the filter designs in:
+    /// https://www.w3.org/TR/audio-eq-cookbook/
+    #[derive(Default)]
+    pub(super) struct Biquad {
+        b0: f64,
+        b1: f64,
+        b2: f64,
+        a1: f64,
+        a2: f64,
+        x1: f64,
+        x2: f64,
+        y1: f64,
+        y2: f64,
+    }
+
+    impl Biquad {
+        pub(super) fn set_band_pass(&mut self, frequency_hz: f64, q: f64, sample_rate: u32) {
+            let sample_rate = sample_rate as f64;
+            let w0 = 2.0 * PI * frequency_hz.clamp(1.0, sample_rate * 0.49) / sample_rate;
+            let alpha = w0.sin() / (2.0 * q);
+            let a0 = 1.0 + alpha;
+            self.b0 = alpha / a0;
+            self.b1 = 0.0;
+            self.b2 = -alpha / a0;
+            self.a1 = (-2.0 * w0.cos()) / a0;
+            self.a2 = (1.0 - alpha) / a0;
+        }
+
+        pub(super) fn set_high_pass(&mut self, frequency_hz: f64, q: f64, sample_rate: u32) {
+            let sample_rate = sample_rate as f64;
+            let w0 = 2.0 * PI * frequency_hz.clamp(1.0, sample_rate * 0.49) / sample_rate;
+            let alpha = w0.sin() / (2.0 * q);
+            let cos_w0 = w0.cos();
+            let a0 = 1.0 + alpha;
+            self.b0 = ((1.0 + cos_w0) / 2.0) / a0;
+            self.b1 = -(1.0 + cos_w0) / a0;
+            self.b2 = ((1.0 + cos_w0) / 2.0) / a0;
+            self.a1 = (-2.0 * cos_w0) / a0;
+            self.a2 = (1.0 - alpha) / a0;
+        }
+
+        pub(super) fn apply(&mut self, x: f64) -> f64 {
+            let y = (self.b0 * x) + (self.b1 * self.x1) + (self.b2 * self.x2)
+                - (self.a1 * self.y1)
+                - (self.a2 * self.y2);
+            self.x2 = self.x1;
+            self.x1 = x;
+            self.y2 = self.y1;
+            self.y1 = y;
+            y
+        }
+    }
+
     struct SignalGen<P> {
         props: P,
         buffer: Buffer,
@@ -355,7 +545,6 @@ pub mod biquad_filter {
         pub mod low_pass {
             pub use super::Props;
             use super::*;
-            use std::f64::consts::PI;
 
             struct UpdateBuffer;
             impl UpdateBufferTrait for UpdateBuffer {
@@ -363,16 +552,10 @@ pub mod biquad_filter {
                     buffer: &mut Buffer,
                     half_power_frequency_sample_rate_ratio: f64,
                 ) {
-                    let a = (PI * half_power_frequency_sample_rate_ratio).tan();
-                    let a2 = a * a;
-                    let n = buffer.entries.len() as f64;
-                    for (i, entry) in buffer.entries.iter_mut().enumerate() {
-                        let r = ((PI * ((2.0 * i as f64) + 1.0)) / (4.0 * n)).sin();
-                        let s = a2 + (2.0 * a * r) + 1.0;
-                        entry.a = a2 / s;
-                        entry.d1 = (2.0 * (1.0 - a2)) / s;
-                        entry.d2 = -(a2 - (2.0 * a * r) + 1.0) / s;
-                    }
+                    update_butterworth_low_pass_entries(
+                        buffer,
+                        half_power_frequency_sample_rate_ratio,
+                    );
                 }
             }
 
@@ -585,92 +768,172 @@ pub mod sample_and_hold {
     }
 }
 
-pub mod clock {
+pub mod slew_limiter {
     use crate::signal::*;
 
     pub struct Props {
-        pub frequency_hz: Sf64,
+        pub signal: Sf64,
+        /// maximum increase of the output per second
+        pub rise_per_second: Sf64,
+        /// maximum decrease of the output per second
+        pub fall_per_second: Sf64,
     }
 
     struct Signal {
         props: Props,
-        state: f64,
+        state: Option<f64>,
     }
 
     impl Signal {
         fn new(props: Props) -> Self {
-            Self { props, state: 0.0 }
+            Self { props, state: None }
         }
     }
 
-    impl SignalTrait<bool> for Signal {
-        fn sample(&mut self, ctx: &SignalCtx) -> bool {
-            self.state = (self.state
-                + (self.props.frequency_hz.sample(ctx) / ctx.sample_rate as f64))
-                .rem_euclid(1.0);
-            self.state < 0.5
+    impl SignalTrait<f64> for Signal {
+        fn sample(&mut self, ctx: &SignalCtx) -> f64 {
+            let input = self.props.signal.sample(ctx);
+            let max_rise = self.props.rise_per_second.sample(ctx) / ctx.sample_rate as f64;
+            let max_fall = self.props.fall_per_second.sample(ctx) / ctx.sample_rate as f64;
+            // start at the first input value rather than sliding up to it
+            let state = self.state.unwrap_or(input);
+            let delta = (input - state).clamp(-max_fall.max(0.0), max_rise.max(0.0));
+            let output = state + delta;
+            self.state = Some(output);
+            output
         }
     }
 
-    pub fn create(props: Props) -> Sbool {
-        Sbool::new(Signal::new(props)).trigger()
+    pub fn create(props: Props) -> Sf64 {
+        Sf64::new(Signal::new(props))
     }
 }
 
-pub mod random_uniform {
+pub mod glide {
     use crate::signal::*;
-    use rand::{Rng, SeedableRng};
-    use rand_xorshift::XorShiftRng;
+
+    pub struct Props {
+        pub frequency_hz: Sf64,
+        pub gate: Sbool,
+        /// time taken to glide between notes an octave apart
+        pub seconds_per_octave: Sf64,
+        /// only glide between notes whose gates overlap, jumping straight to the frequency of
+        /// notes that start after the previous note was released
+        pub legato_only: bool,
+    }
 
     struct Signal {
-        rng: XorShiftRng,
+        props: Props,
+        // current output frequency in octaves relative to 1Hz
+        octaves: Option<f64>,
+        prev_gate: bool,
     }
 
     impl Signal {
-        fn new() -> Self {
+        fn new(props: Props) -> Self {
             Self {
-                rng: XorShiftRng::from_entropy(),
+                props,
+                octaves: None,
+                prev_gate: false,
             }
         }
     }
 
     impl SignalTrait<f64> for Signal {
-        fn sample(&mut self, _ctx: &SignalCtx) -> f64 {
-            self.rng.gen()
+        fn sample(&mut self, ctx: &SignalCtx) -> f64 {
+            let frequency_hz = self.props.frequency_hz.sample(ctx);
+            let gate = self.props.gate.sample(ctx);
+            let seconds_per_octave = self.props.seconds_per_octave.sample(ctx);
+            if frequency_hz <= 0.0 {
+                self.prev_gate = gate;
+                return frequency_hz;
+            }
+            let target = frequency_hz.log2();
+            let glide = !self.props.legato_only || (gate && self.prev_gate);
+            self.prev_gate = gate;
+            let octaves = match self.octaves {
+                Some(octaves) if glide && seconds_per_octave > 0.0 => {
+                    let max_delta = 1.0 / (seconds_per_octave * ctx.sample_rate as f64);
+                    octaves + (target - octaves).clamp(-max_delta, max_delta)
+                }
+                _ => target,
+            };
+            self.octaves = Some(octaves);
+            octaves.exp2()
         }
     }
 
-    pub fn create() -> Sf64 {
-        Sf64::new(Signal::new())
+    pub fn create(props: Props) -> Sf64 {
+        Sf64::new(Signal::new(props))
     }
 }
 
-pub mod synth_sequencer {
-    use crate::signal::*;
+pub mod quantize {
+    use crate::{
+        music::{note, NoteName, Scale},
+        signal::*,
+        tuning::Tuning,
+    };
 
-    pub struct Step {
-        pub frequency_hz: Sf64,
-        pub period_seconds: Sf64,
-    }
+    // Range of keys of the tuning which may be output
+    const MIN_KEY: i32 = 0;
+    const MAX_KEY: i32 = 127;
 
     pub struct Props {
-        pub sequence: Vec<Step>,
-        pub clock: Sbool,
+        pub frequency_hz: Sf64,
+        /// the notes of the scale are applied to the keys of the tuning, assuming there are 12
+        /// keys in each octave as on a piano keyboard
+        pub scale: Scale,
+        pub root: NoteName,
+        pub tuning: Tuning,
     }
 
     struct Signal {
         props: Props,
-        step_index: usize,
-        gate_remain_seconds: f64,
+        // log2 of the frequency of each key in the scale along with its key, sorted by frequency
+        keys: Vec<(f64, i32)>,
+        prev_key: Option<i32>,
     }
 
     impl Signal {
         fn new(props: Props) -> Self {
-            // the sequence will start on the first clock pulse
+            let root_key = note(props.root, 0).midi_number();
+            let mut keys = (MIN_KEY..=MAX_KEY)
+                .filter(|&key| {
+                    let semitones = (key - root_key).rem_euclid(12) as u32;
+                    props.scale.semitones().contains(&semitones)
+                })
+                .filter_map(|key| {
+                    props
+                        .tuning
+                        .frequency(key)
+                        .filter(|&frequency_hz| frequency_hz > 0.0)
+                        .map(|frequency_hz| (frequency_hz.log2(), key))
+                })
+                .collect::<Vec<_>>();
+            keys.sort_by(|a, b| a.0.total_cmp(&b.0));
             Self {
-                gate_remain_seconds: 0.0,
-                step_index: props.sequence.len() - 1,
                 props,
+                keys,
+                prev_key: None,
+            }
+        }
+
+        // The frequency (as log2) and key closest to a frequency (as log2)
+        fn nearest(&self, log2_frequency_hz: f64) -> Option<(f64, i32)> {
+            let index = self.keys.partition_point(|&(x, _)| x < log2_frequency_hz);
+            let below = index.checked_sub(1).and_then(|i| self.keys.get(i));
+            let above = self.keys.get(index);
+            match (below, above) {
+                (Some(&below), Some(&above)) => {
+                    if log2_frequency_hz - below.0 <= above.0 - log2_frequency_hz {
+                        Some(below)
+                    } else {
+                        Some(above)
+                    }
+                }
+                (Some(&nearest), None) | (None, Some(&nearest)) => Some(nearest),
+                (None, None) => None,
             }
         }
     }
@@ -678,28 +941,37 @@ pub mod synth_sequencer {
     #[derive(Clone)]
     struct OutputSample {
         frequency_hz: f64,
-        gate: bool,
+        trigger: bool,
     }
 
     pub struct Output {
         pub frequency_hz: Sf64,
-        pub gate: Sbool,
+        /// triggered whenever the output note changes
+        pub trigger: Sbool,
     }
 
     impl SignalTrait<OutputSample> for Signal {
         fn sample(&mut self, ctx: &SignalCtx) -> OutputSample {
-            let current_step = if self.props.clock.sample(ctx) {
-                self.step_index = (self.step_index + 1) % self.props.sequence.len();
-                let current_step = &mut self.props.sequence[self.step_index];
-                self.gate_remain_seconds = current_step.period_seconds.sample(ctx);
-                current_step
+            let frequency_hz = self.props.frequency_hz.sample(ctx);
+            let nearest = if frequency_hz > 0.0 {
+                self.nearest(frequency_hz.log2())
             } else {
-                &mut self.props.sequence[self.step_index]
+                None
             };
-            self.gate_remain_seconds -= 1.0 / ctx.sample_rate as f64;
-            let gate = self.gate_remain_seconds >= 0.0;
-            let frequency_hz = current_step.frequency_hz.sample(ctx);
-            OutputSample { frequency_hz, gate }
+            match nearest {
+                Some((log2_frequency_hz, key)) => {
+                    let trigger = self.prev_key != Some(key);
+                    self.prev_key = Some(key);
+                    OutputSample {
+                        frequency_hz: log2_frequency_hz.exp2(),
+                        trigger,
+                    }
+                }
+                None => OutputSample {
+                    frequency_hz: 0.0,
+                    trigger: false,
+                },
+            }
         }
     }
 
@@ -707,88 +979,2741 @@ pub mod synth_sequencer {
         let combined_signal = BufferedSignal::new(Signal::new(props));
         Output {
             frequency_hz: combined_signal.map(|s| s.frequency_hz),
-            gate: combined_signal.map(|s| s.gate),
+            trigger: combined_signal.map(|s| s.trigger),
         }
     }
 }
 
-pub mod trigger_sequencer_8 {
+pub mod clock {
     use crate::signal::*;
 
     pub struct Props {
-        pub sequence: Vec<Su8>,
-        pub clock: Sbool,
+        pub frequency_hz: Sf64,
     }
 
     struct Signal {
         props: Props,
-        step_index: usize,
+        state: f64,
     }
 
     impl Signal {
         fn new(props: Props) -> Self {
-            // the sequence will start on the first clock pulse
-            Self {
-                step_index: props.sequence.len() - 1,
-                props,
-            }
+            Self { props, state: 0.0 }
         }
     }
 
-    impl SignalTrait<u8> for Signal {
-        fn sample(&mut self, ctx: &SignalCtx) -> u8 {
-            if self.props.clock.sample(ctx) {
-                self.step_index = (self.step_index + 1) % self.props.sequence.len();
-                self.props.sequence[self.step_index].sample(ctx)
-            } else {
-                0
-            }
+    impl SignalTrait<bool> for Signal {
+        fn sample(&mut self, ctx: &SignalCtx) -> bool {
+            self.state = (self.state
+                + (self.props.frequency_hz.sample(ctx) / ctx.sample_rate as f64))
+                .rem_euclid(1.0);
+            self.state < 0.5
         }
     }
 
-    pub fn create(props: Props) -> [Sbool; 8] {
-        Su8::new(Signal::new(props)).expand()
+    pub fn create(props: Props) -> Sbool {
+        Sbool::new(Signal::new(props)).trigger()
     }
-}
 
-pub mod sample_player {
-    use crate::signal::*;
+    pub mod divide {
+        use crate::signal::*;
 
-    pub struct Props {
-        /// monophonic data at the same sample rate as the audio device
-        pub data: Vec<f32>,
-        pub trigger: Sbool,
+        pub struct Props {
+            pub clock: Sbool,
+            /// number of input pulses per output pulse
+            pub divisor: Sf64,
+            /// the next input pulse after a reset is passed through
+            pub reset: Sbool,
+        }
+
+        struct Signal {
+            props: Props,
+            count: u64,
+        }
+
+        impl Signal {
+            fn new(props: Props) -> Self {
+                Self { props, count: 0 }
+            }
+        }
+
+        impl SignalTrait<bool> for Signal {
+            fn sample(&mut self, ctx: &SignalCtx) -> bool {
+                let divisor = self.props.divisor.sample(ctx).round().max(1.0) as u64;
+                if self.props.reset.sample(ctx) {
+                    self.count = 0;
+                }
+                if self.props.clock.sample(ctx) {
+                    let output = self.count.is_multiple_of(divisor);
+                    self.count += 1;
+                    output
+                } else {
+                    false
+                }
+            }
+        }
+
+        pub fn create(props: Props) -> Sbool {
+            Sbool::new(Signal::new(props))
+        }
     }
 
-    struct Signal {
-        props: Props,
-        sample_index: usize,
+    pub mod multiply {
+        use crate::signal::*;
+
+        pub struct Props {
+            pub clock: Sbool,
+            /// number of output pulses per input pulse
+            pub factor: Sf64,
+            /// restarts the output pulses as though an input pulse had just occurred
+            pub reset: Sbool,
+        }
+
+        struct Signal {
+            props: Props,
+            // number of samples between the two most recent input pulses
+            period_samples: Option<u64>,
+            samples_since_pulse: u64,
+            next_pulse_index: u64,
+        }
+
+        impl Signal {
+            fn new(props: Props) -> Self {
+                Self {
+                    props,
+                    period_samples: None,
+                    samples_since_pulse: 0,
+                    next_pulse_index: 1,
+                }
+            }
+        }
+
+        impl SignalTrait<bool> for Signal {
+            fn sample(&mut self, ctx: &SignalCtx) -> bool {
+                let factor = self.props.factor.sample(ctx).round().max(1.0) as u64;
+                let reset = self.props.reset.sample(ctx);
+                if self.props.clock.sample(ctx) {
+                    if self.samples_since_pulse > 0 {
+                        self.period_samples = Some(self.samples_since_pulse);
+                    }
+                    self.samples_since_pulse = 0;
+                    self.next_pulse_index = 1;
+                    return true;
+                }
+                if reset {
+                    self.samples_since_pulse = 0;
+                    self.next_pulse_index = 1;
+                    return true;
+                }
+                self.samples_since_pulse += 1;
+                // no output pulses can be generated until the period of the input is known
+                if let Some(period_samples) = self.period_samples {
+                    let next_pulse_samples =
+                        (period_samples * self.next_pulse_index) as f64 / factor as f64;
+                    if self.next_pulse_index < factor
+                        && self.samples_since_pulse as f64 >= next_pulse_samples
+                    {
+                        self.next_pulse_index += 1;
+                        return true;
+                    }
+                }
+                false
+            }
+        }
+
+        pub fn create(props: Props) -> Sbool {
+            Sbool::new(Signal::new(props))
+        }
     }
 
-    impl Signal {
-        fn new(props: Props) -> Self {
-            Self {
-                sample_index: props.data.len(),
-                props,
+    pub mod swing {
+        use crate::signal::*;
+
+        pub struct Props {
+            pub clock: Sbool,
+            /// 0 leaves the clock unchanged and 1 delays every other pulse by half the period
+            /// between pulses
+            pub amount_01: Sf64,
+        }
+
+        struct Signal {
+            props: Props,
+            count: u64,
+            period_samples: u64,
+            samples_since_pulse: u64,
+            // number of samples until the delayed pulse is output
+            delayed_pulse_remain: Option<u64>,
+        }
+
+        impl Signal {
+            fn new(props: Props) -> Self {
+                Self {
+                    props,
+                    count: 0,
+                    period_samples: 0,
+                    samples_since_pulse: 0,
+                    delayed_pulse_remain: None,
+                }
+            }
+        }
+
+        impl SignalTrait<bool> for Signal {
+            fn sample(&mut self, ctx: &SignalCtx) -> bool {
+                let amount_01 = self.props.amount_01.sample(ctx).clamp(0.0, 1.0);
+                let mut output = false;
+                if let Some(remain) = self.delayed_pulse_remain {
+                    if remain == 0 {
+                        self.delayed_pulse_remain = None;
+                        output = true;
+                    } else {
+                        self.delayed_pulse_remain = Some(remain - 1);
+                    }
+                }
+                self.samples_since_pulse += 1;
+                if self.props.clock.sample(ctx) {
+                    self.period_samples = self.samples_since_pulse;
+                    self.samples_since_pulse = 0;
+                    if self.count.is_multiple_of(2) {
+                        output = true;
+                    } else {
+                        let delay = (amount_01 * self.period_samples as f64 * 0.5) as u64;
+                        if delay == 0 {
+                            output = true;
+                        } else {
+                            self.delayed_pulse_remain = Some(delay - 1);
+                        }
+                    }
+                    self.count += 1;
+                }
+                output
             }
         }
+
+        pub fn create(props: Props) -> Sbool {
+            Sbool::new(Signal::new(props))
+        }
     }
 
-    impl SignalTrait<f32> for Signal {
-        fn sample(&mut self, ctx: &SignalCtx) -> f32 {
-            if self.props.trigger.sample(ctx) {
-                self.sample_index = 0;
+    pub mod transport {
+        use crate::signal::*;
+
+        pub struct Props {
+            pub bpm: Sf64,
+            pub beats_per_bar: Sf64,
+            /// number of step pulses per beat
+            pub steps_per_beat: Sf64,
+        }
+
+        struct Signal {
+            props: Props,
+            phase_01: f64,
+            step_count: u64,
+        }
+
+        impl Signal {
+            fn new(props: Props) -> Self {
+                Self {
+                    props,
+                    // the first step starts on the first sample
+                    phase_01: 1.0,
+                    step_count: 0,
+                }
             }
-            if let Some(&sample) = self.props.data.get(self.sample_index) {
-                self.sample_index += 1;
-                sample
-            } else {
-                0.0
+        }
+
+        #[derive(Clone, Default)]
+        struct OutputSample {
+            step: bool,
+            beat: bool,
+            bar_start: bool,
+        }
+
+        pub struct Output {
+            pub step: Sbool,
+            pub beat: Sbool,
+            pub bar_start: Sbool,
+        }
+
+        impl SignalTrait<OutputSample> for Signal {
+            fn sample(&mut self, ctx: &SignalCtx) -> OutputSample {
+                let bpm = self.props.bpm.sample(ctx);
+                let beats_per_bar = self.props.beats_per_bar.sample(ctx).round().max(1.0) as u64;
+                let steps_per_beat = self.props.steps_per_beat.sample(ctx).round().max(1.0) as u64;
+                let output = if self.phase_01 >= 1.0 {
+                    self.phase_01 = self.phase_01.rem_euclid(1.0);
+                    let step_count = self.step_count;
+                    self.step_count += 1;
+                    OutputSample {
+                        step: true,
+                        beat: step_count.is_multiple_of(steps_per_beat),
+                        bar_start: step_count.is_multiple_of(steps_per_beat * beats_per_bar),
+                    }
+                } else {
+                    OutputSample::default()
+                };
+                self.phase_01 += (bpm * steps_per_beat as f64) / (60.0 * ctx.sample_rate as f64);
+                output
+            }
+        }
+
+        pub fn create(props: Props) -> Output {
+            let combined_signal = BufferedSignal::new(Signal::new(props));
+            Output {
+                step: combined_signal.map(|s| s.step),
+                beat: combined_signal.map(|s| s.beat),
+                bar_start: combined_signal.map(|s| s.bar_start),
             }
         }
     }
+}
 
-    pub fn create(props: Props) -> Sf32 {
-        Sf32::new(Signal::new(props))
+pub mod random_uniform {
+    use crate::signal::*;
+    use rand::{Rng, SeedableRng};
+    use rand_xorshift::XorShiftRng;
+
+    /// Random number generator which produces the same sequence each time it's created with the
+    /// same seed, or which is seeded from entropy if the seed is `None`
+    pub(super) fn rng(seed: Option<u64>) -> XorShiftRng {
+        match seed {
+            Some(seed) => XorShiftRng::seed_from_u64(seed),
+            None => XorShiftRng::from_entropy(),
+        }
+    }
+
+    pub struct Props {
+        pub seed: Option<u64>,
+    }
+
+    struct Signal {
+        rng: XorShiftRng,
+    }
+
+    impl Signal {
+        fn new(props: Props) -> Self {
+            Self {
+                rng: rng(props.seed),
+            }
+        }
+    }
+
+    impl SignalTrait<f64> for Signal {
+        fn sample(&mut self, _ctx: &SignalCtx) -> f64 {
+            self.rng.gen()
+        }
+    }
+
+    pub fn create(props: Props) -> Sf64 {
+        Sf64::new(Signal::new(props))
+    }
+}
+
+pub mod synth_sequencer {
+    use crate::signal::*;
+
+    pub struct Step {
+        pub frequency_hz: Sf64,
+        pub period_seconds: Sf64,
+    }
+
+    pub struct Props {
+        pub sequence: Vec<Step>,
+        pub clock: Sbool,
+    }
+
+    struct Signal {
+        props: Props,
+        step_index: usize,
+        gate_remain_seconds: f64,
+    }
+
+    impl Signal {
+        fn new(props: Props) -> Self {
+            // the sequence will start on the first clock pulse
+            Self {
+                gate_remain_seconds: 0.0,
+                step_index: props.sequence.len() - 1,
+                props,
+            }
+        }
+    }
+
+    #[derive(Clone)]
+    struct OutputSample {
+        frequency_hz: f64,
+        gate: bool,
+    }
+
+    pub struct Output {
+        pub frequency_hz: Sf64,
+        pub gate: Sbool,
+    }
+
+    impl SignalTrait<OutputSample> for Signal {
+        fn sample(&mut self, ctx: &SignalCtx) -> OutputSample {
+            let current_step = if self.props.clock.sample(ctx) {
+                self.step_index = (self.step_index + 1) % self.props.sequence.len();
+                let current_step = &mut self.props.sequence[self.step_index];
+                self.gate_remain_seconds = current_step.period_seconds.sample(ctx);
+                current_step
+            } else {
+                &mut self.props.sequence[self.step_index]
+            };
+            self.gate_remain_seconds -= 1.0 / ctx.sample_rate as f64;
+            let gate = self.gate_remain_seconds >= 0.0;
+            let frequency_hz = current_step.frequency_hz.sample(ctx);
+            OutputSample { frequency_hz, gate }
+        }
+    }
+
+    pub fn create(props: Props) -> Output {
+        let combined_signal = BufferedSignal::new(Signal::new(props));
+        Output {
+            frequency_hz: combined_signal.map(|s| s.frequency_hz),
+            gate: combined_signal.map(|s| s.gate),
+        }
+    }
+}
+
+pub mod step_sequencer {
+    use super::random_uniform::rng;
+    use crate::signal::*;
+    use rand::Rng;
+    use rand_xorshift::XorShiftRng;
+
+    pub struct Step {
+        pub frequency_hz: Sf64,
+        /// duration of the gate
+        pub period_seconds: Sf64,
+        pub velocity_01: Sf64,
+        /// the gate stays closed during this step
+        pub rest: bool,
+        /// hold the gate until the next step and don't retrigger it at the start of the next step
+        pub tie: bool,
+        /// time taken for the frequency to slide from the previous step's frequency
+        pub slide_seconds: Sf64,
+        /// probability that this step is played, otherwise it's treated as a rest
+        pub probability_01: Sf64,
+    }
+
+    impl Step {
+        pub fn new(frequency_hz: Sf64, period_seconds: Sf64) -> Self {
+            Self {
+                frequency_hz,
+                period_seconds,
+                velocity_01: Const::new(1.0).buffered_signal(),
+                rest: false,
+                tie: false,
+                slide_seconds: Const::new(0.0).buffered_signal(),
+                probability_01: Const::new(1.0).buffered_signal(),
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Direction {
+        Forward,
+        Reverse,
+        /// back and forth without repeating the first and last steps
+        Pendulum,
+        Random,
+    }
+
+    pub struct Props {
+        pub sequence: Vec<Step>,
+        pub clock: Sbool,
+        /// number of steps from the start of the sequence to play
+        pub length: Sf64,
+        pub direction: Direction,
+        pub seed: Option<u64>,
+    }
+
+    struct Signal {
+        props: Props,
+        rng: XorShiftRng,
+        step_count: usize,
+        // index of the most recently played step, which determines the output frequency
+        note_step_index: usize,
+        gate_remain_seconds: f64,
+        tied: bool,
+        velocity_01: f64,
+        frequency_hz: f64,
+        slide_from_hz: f64,
+        slide_elapsed_seconds: f64,
+    }
+
+    impl Signal {
+        fn new(props: Props) -> Self {
+            Self {
+                rng: rng(props.seed),
+                props,
+                step_count: 0,
+                note_step_index: 0,
+                gate_remain_seconds: 0.0,
+                tied: false,
+                velocity_01: 0.0,
+                frequency_hz: 0.0,
+                slide_from_hz: 0.0,
+                slide_elapsed_seconds: 0.0,
+            }
+        }
+
+        fn next_step_index(&mut self, length: usize) -> usize {
+            let step = self.step_count;
+            self.step_count += 1;
+            match self.props.direction {
+                Direction::Forward => step % length,
+                Direction::Reverse => length - 1 - (step % length),
+                Direction::Pendulum => {
+                    if length == 1 {
+                        0
+                    } else {
+                        let i = step % (2 * (length - 1));
+                        if i < length {
+                            i
+                        } else {
+                            2 * (length - 1) - i
+                        }
+                    }
+                }
+                Direction::Random => self.rng.gen_range(0..length),
+            }
+        }
+    }
+
+    #[derive(Clone)]
+    struct OutputSample {
+        frequency_hz: f64,
+        gate: bool,
+        velocity_01: f64,
+    }
+
+    pub struct Output {
+        pub frequency_hz: Sf64,
+        pub gate: Sbool,
+        pub velocity_01: Sf64,
+    }
+
+    impl SignalTrait<OutputSample> for Signal {
+        fn sample(&mut self, ctx: &SignalCtx) -> OutputSample {
+            let length = (self.props.length.sample(ctx).round() as usize)
+                .clamp(1, self.props.sequence.len());
+            let mut retrigger = false;
+            if self.props.clock.sample(ctx) {
+                let step_index = self.next_step_index(length);
+                let step = &mut self.props.sequence[step_index];
+                let probability_01 = step.probability_01.sample(ctx);
+                let play = !step.rest && self.rng.gen::<f64>() < probability_01;
+                if play {
+                    // a step following a tied step continues the previous note
+                    retrigger = !self.tied;
+                    self.velocity_01 = step.velocity_01.sample(ctx);
+                    self.gate_remain_seconds = if step.tie {
+                        f64::INFINITY
+                    } else {
+                        step.period_seconds.sample(ctx)
+                    };
+                    self.slide_from_hz = self.frequency_hz;
+                    self.slide_elapsed_seconds = 0.0;
+                    self.tied = step.tie;
+                    self.note_step_index = step_index;
+                } else {
+                    self.gate_remain_seconds = 0.0;
+                    self.tied = false;
+                }
+            }
+            let step = &mut self.props.sequence[self.note_step_index];
+            let target_hz = step.frequency_hz.sample(ctx);
+            let slide_seconds = step.slide_seconds.sample(ctx);
+            // slide in log-frequency so each octave takes the same time
+            self.frequency_hz = if self.slide_elapsed_seconds < slide_seconds
+                && self.slide_from_hz > 0.0
+                && target_hz > 0.0
+            {
+                let progress_01 = self.slide_elapsed_seconds / slide_seconds;
+                self.slide_from_hz * (target_hz / self.slide_from_hz).powf(progress_01)
+            } else {
+                target_hz
+            };
+            self.slide_elapsed_seconds += 1.0 / ctx.sample_rate as f64;
+            self.gate_remain_seconds -= 1.0 / ctx.sample_rate as f64;
+            OutputSample {
+                frequency_hz: self.frequency_hz,
+                // the gate is briefly released at the start of each note so that it's retriggered
+                gate: self.gate_remain_seconds >= 0.0 && !retrigger,
+                velocity_01: self.velocity_01,
+            }
+        }
+    }
+
+    /// Panics if the sequence is empty
+    pub fn create(props: Props) -> Output {
+        assert!(!props.sequence.is_empty(), "sequence must not be empty");
+        let combined_signal = BufferedSignal::new(Signal::new(props));
+        Output {
+            frequency_hz: combined_signal.map(|s| s.frequency_hz),
+            gate: combined_signal.map(|s| s.gate),
+            velocity_01: combined_signal.map(|s| s.velocity_01),
+        }
+    }
+
+    #[cfg(test)]
+    mod test {
+        use super::*;
+        use crate::dsl::{const_, var};
+
+        // Number of samples between clock pulses, at a sample rate of 4Hz
+        const CLOCK_PERIOD_SAMPLES: u64 = 4;
+
+        fn steps(frequencies_hz: &[f64]) -> Vec<Step> {
+            frequencies_hz
+                .iter()
+                .map(|&frequency_hz| Step::new(const_(frequency_hz), const_(0.5)))
+                .collect()
+        }
+
+        fn signal(sequence: Vec<Step>, length: f64, direction: Direction) -> (Signal, Var<bool>) {
+            let (clock, clock_var) = var(false);
+            let signal = Signal::new(Props {
+                sequence,
+                clock,
+                length: const_(length),
+                direction,
+                seed: Some(0),
+            });
+            (signal, clock_var)
+        }
+
+        // Plays a sequence with a clock pulse every `CLOCK_PERIOD_SAMPLES` samples, returning the
+        // frequency of each sample and the gate drawn as a string
+        fn play(sequence: Vec<Step>, num_samples: u64) -> (Vec<f64>, String) {
+            let length = sequence.len() as f64;
+            let (mut signal, clock_var) = signal(sequence, length, Direction::Forward);
+            let mut frequencies_hz = Vec::new();
+            let mut gate = String::new();
+            for sample_index in 0..num_samples {
+                clock_var.set(sample_index % CLOCK_PERIOD_SAMPLES == 0);
+                let output = signal.sample(&SignalCtx {
+                    sample_index,
+                    sample_rate: CLOCK_PERIOD_SAMPLES as u32,
+                });
+                frequencies_hz.push(output.frequency_hz);
+                gate.push(if output.gate { 'x' } else { '.' });
+            }
+            (frequencies_hz, gate)
+        }
+
+        fn step_indices(length: f64, direction: Direction, n: usize) -> Vec<usize> {
+            let (mut signal, _) = signal(steps(&[100.0; 4]), length, direction);
+            let length = length as usize;
+            (0..n).map(|_| signal.next_step_index(length)).collect()
+        }
+
+        #[test]
+        fn rest() {
+            let mut sequence = steps(&[100.0, 200.0, 300.0]);
+            sequence[1].rest = true;
+            let (frequencies_hz, gate) = play(sequence, 12);
+            assert_eq!(gate, ".x.......x..");
+            // the frequency of the note before the rest is held during the rest
+            assert_eq!(&frequencies_hz[4..8], &[100.0; 4]);
+            assert_eq!(frequencies_hz[8], 300.0);
+        }
+
+        #[test]
+        fn tie() {
+            let mut sequence = steps(&[100.0, 200.0, 300.0]);
+            sequence[0].tie = true;
+            let (frequencies_hz, gate) = play(sequence, 12);
+            // the gate is held into the step after the tie without being retriggered
+            assert_eq!(gate, ".xxxxx...x..");
+            assert_eq!(frequencies_hz[4], 200.0);
+        }
+
+        #[test]
+        fn slide() {
+            let mut sequence = steps(&[100.0, 400.0]);
+            sequence[1].slide_seconds = const_(1.0);
+            let (frequencies_hz, _) = play(sequence, 9);
+            assert_eq!(&frequencies_hz[..4], &[100.0; 4]);
+            // two octaves over one second is one octave every 2 samples
+            assert_eq!(frequencies_hz[4], 100.0);
+            assert!((frequencies_hz[6] - 200.0).abs() < 1e-9);
+            assert!(frequencies_hz[5] > 100.0 && frequencies_hz[5] < 200.0);
+            assert!(frequencies_hz[7] > 200.0 && frequencies_hz[7] < 400.0);
+            // the first step doesn't slide
+            assert_eq!(frequencies_hz[8], 100.0);
+        }
+
+        #[test]
+        fn length() {
+            for (length, expected) in [
+                (2.0, vec![0, 1, 0, 1, 0]),
+                (10.0, vec![0, 1, 2, 3, 0]),
+                (0.0, vec![0, 0, 0, 0, 0]),
+            ] {
+                let (mut signal, clock_var) =
+                    signal(steps(&[100.0; 4]), length, Direction::Forward);
+                clock_var.set(true);
+                let indices = (0..5)
+                    .map(|sample_index| {
+                        signal.sample(&SignalCtx {
+                            sample_index,
+                            sample_rate: 4,
+                        });
+                        signal.note_step_index
+                    })
+                    .collect::<Vec<_>>();
+                assert_eq!(indices, expected, "{length}");
+            }
+        }
+
+        #[test]
+        fn directions() {
+            assert_eq!(
+                step_indices(3.0, Direction::Forward, 7),
+                vec![0, 1, 2, 0, 1, 2, 0]
+            );
+            assert_eq!(
+                step_indices(3.0, Direction::Reverse, 7),
+                vec![2, 1, 0, 2, 1, 0, 2]
+            );
+            assert_eq!(
+                step_indices(3.0, Direction::Pendulum, 7),
+                vec![0, 1, 2, 1, 0, 1, 2]
+            );
+            assert_eq!(step_indices(1.0, Direction::Pendulum, 3), vec![0, 0, 0]);
+            let random = step_indices(3.0, Direction::Random, 32);
+            assert!(random.iter().all(|&i| i < 3));
+            assert_eq!(random, step_indices(3.0, Direction::Random, 32));
+        }
+
+        #[test]
+        #[should_panic(expected = "sequence must not be empty")]
+        fn empty_sequence() {
+            create(Props {
+                sequence: Vec::new(),
+                clock: const_(false),
+                length: const_(1.0),
+                direction: Direction::Forward,
+                seed: None,
+            });
+        }
+    }
+}
+
+pub mod arpeggiator {
+    use super::{random_uniform::rng, synth_sequencer::Output};
+    use crate::signal::*;
+    use rand::Rng;
+    use rand_xorshift::XorShiftRng;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Order {
+        Up,
+        Down,
+        /// up and then down again without repeating the highest and lowest notes
+        UpDown,
+        Random,
+        /// the order in which the notes were pressed
+        AsPlayed,
+    }
+
+    pub struct Note {
+        pub frequency_hz: Sf64,
+        pub gate: Sbool,
+    }
+
+    pub struct Props {
+        pub notes: Vec<Note>,
+        pub clock: Sbool,
+        pub order: Order,
+        /// number of octaves over which the held notes are repeated
+        pub octave_range: Sf64,
+        /// proportion of the period between clock pulses for which the gate is held
+        pub gate_length_01: Sf64,
+        /// keep playing notes after they are released until a new note is pressed
+        pub latch: bool,
+        pub seed: Option<u64>,
+    }
+
+    struct Signal {
+        props: Props,
+        rng: XorShiftRng,
+        frequencies_hz: Vec<f64>,
+        gates: Vec<bool>,
+        prev_gates: Vec<bool>,
+        // indices of held (or latched) notes in the order they were pressed
+        held: Vec<usize>,
+        // indices of held notes in the order they are played within each octave
+        ordered_held: Vec<usize>,
+        step_count: usize,
+        // number of samples between the two most recent clock pulses, or `None` if fewer than
+        // two pulses have occurred
+        period_samples: Option<u64>,
+        samples_since_pulse: Option<u64>,
+        gate_remain_samples: f64,
+        frequency_hz: f64,
+    }
+
+    impl Signal {
+        fn new(props: Props) -> Self {
+            let num_notes = props.notes.len();
+            Self {
+                rng: rng(props.seed),
+                props,
+                frequencies_hz: vec![0.0; num_notes],
+                gates: vec![false; num_notes],
+                prev_gates: vec![false; num_notes],
+                held: Vec::with_capacity(num_notes),
+                ordered_held: Vec::with_capacity(num_notes),
+                step_count: 0,
+                period_samples: None,
+                samples_since_pulse: None,
+                gate_remain_samples: 0.0,
+                frequency_hz: 0.0,
+            }
+        }
+
+        fn update_held(&mut self, ctx: &SignalCtx) {
+            let any_pressed_before = self.prev_gates.iter().any(|&g| g);
+            for (i, note) in self.props.notes.iter_mut().enumerate() {
+                self.frequencies_hz[i] = note.frequency_hz.sample(ctx);
+                self.gates[i] = note.gate.sample(ctx);
+            }
+            let any_new_press = self
+                .gates
+                .iter()
+                .zip(self.prev_gates.iter())
+                .any(|(&gate, &prev_gate)| gate && !prev_gate);
+            // pressing notes after releasing every key replaces the latched notes
+            if self.props.latch && any_new_press && !any_pressed_before {
+                self.held.clear();
+            }
+            for i in 0..self.gates.len() {
+                let gate = self.gates[i];
+                if gate && !self.prev_gates[i] {
+                    if !self.held.contains(&i) {
+                        self.held.push(i);
+                    }
+                } else if !gate && self.prev_gates[i] && !self.props.latch {
+                    self.held.retain(|&j| j != i);
+                }
+                self.prev_gates[i] = gate;
+            }
+        }
+
+        fn next_frequency_hz(&mut self, octave_range: usize) -> Option<f64> {
+            // reuse a buffer allocated up front so that clock pulses don't allocate
+            self.ordered_held.clear();
+            self.ordered_held.extend_from_slice(&self.held);
+            if self.props.order != Order::AsPlayed {
+                let frequencies_hz = &self.frequencies_hz;
+                self.ordered_held.sort_unstable_by(|&a, &b| {
+                    frequencies_hz[a]
+                        .total_cmp(&frequencies_hz[b])
+                        .then(a.cmp(&b))
+                });
+            }
+            let num_held = self.ordered_held.len();
+            // the held notes are repeated in each octave of the range
+            let n = num_held.saturating_mul(octave_range);
+            if n == 0 {
+                return None;
+            }
+            let step = self.step_count;
+            self.step_count += 1;
+            let index = match self.props.order {
+                Order::Up | Order::AsPlayed => step % n,
+                Order::Down => n - 1 - (step % n),
+                Order::UpDown => {
+                    if n == 1 {
+                        0
+                    } else {
+                        let i = step % (2 * (n - 1));
+                        if i < n {
+                            i
+                        } else {
+                            2 * (n - 1) - i
+                        }
+                    }
+                }
+                Order::Random => self.rng.gen_range(0..n),
+            };
+            let frequency_hz = self.frequencies_hz[self.ordered_held[index % num_held]];
+            Some(frequency_hz * 2_f64.powi((index / num_held) as i32))
+        }
+    }
+
+    #[derive(Clone)]
+    struct OutputSample {
+        frequency_hz: f64,
+        gate: bool,
+    }
+
+    impl SignalTrait<OutputSample> for Signal {
+        fn sample(&mut self, ctx: &SignalCtx) -> OutputSample {
+            self.update_held(ctx);
+            let octave_range = self.props.octave_range.sample(ctx).round().max(1.0) as usize;
+            let gate_length_01 = self.props.gate_length_01.sample(ctx).clamp(0.0, 1.0);
+            self.samples_since_pulse = self.samples_since_pulse.map(|x| x + 1);
+            let clock = self.props.clock.sample(ctx);
+            if clock {
+                if let Some(samples_since_pulse) = self.samples_since_pulse {
+                    self.period_samples = Some(samples_since_pulse);
+                }
+                self.samples_since_pulse = Some(0);
+                if let Some(frequency_hz) = self.next_frequency_hz(octave_range) {
+                    self.frequency_hz = frequency_hz;
+                    // until the clock period is known the gate is held until the next pulse
+                    self.gate_remain_samples = self
+                        .period_samples
+                        .map_or(f64::INFINITY, |x| gate_length_01 * x as f64);
+                } else {
+                    // restart the pattern the next time notes are held
+                    self.step_count = 0;
+                }
+            }
+            if self.held.is_empty() {
+                self.gate_remain_samples = 0.0;
+            }
+            self.gate_remain_samples -= 1.0;
+            OutputSample {
+                frequency_hz: self.frequency_hz,
+                // the gate is briefly released on each clock pulse so that each note is retriggered
+                gate: self.gate_remain_samples >= 0.0 && !clock,
+            }
+        }
+    }
+
+    pub fn create(props: Props) -> Output {
+        let combined_signal = BufferedSignal::new(Signal::new(props));
+        Output {
+            frequency_hz: combined_signal.map(|s| s.frequency_hz),
+            gate: combined_signal.map(|s| s.gate),
+        }
+    }
+
+    #[cfg(test)]
+    mod test {
+        use super::*;
+        use crate::dsl::{const_, var};
+
+        #[test]
+        fn latch_chord_pressed_in_one_sample() {
+            let (gate, gate_var) = var(false);
+            let notes = [100.0, 200.0, 300.0]
+                .into_iter()
+                .map(|frequency_hz| Note {
+                    frequency_hz: const_(frequency_hz),
+                    gate: gate.clone_ref(),
+                })
+                .collect();
+            let (clock, clock_var) = var(false);
+            let mut signal = Signal::new(Props {
+                notes,
+                clock,
+                order: Order::Up,
+                octave_range: const_(1.0),
+                gate_length_01: const_(0.5),
+                latch: true,
+                seed: Some(0),
+            });
+            let mut ctx = SignalCtx {
+                sample_index: 0,
+                sample_rate: 44100,
+            };
+            let mut frequencies_hz = Vec::new();
+            for i in 0..8 {
+                // every note is pressed in the first sample and released in the second
+                gate_var.set(i == 0);
+                clock_var.set(i >= 2);
+                frequencies_hz.push(signal.sample(&ctx).frequency_hz);
+                ctx.sample_index += 1;
+            }
+            assert_eq!(signal.held, vec![0, 1, 2]);
+            assert_eq!(&frequencies_hz[2..5], &[100.0, 200.0, 300.0]);
+        }
+
+        #[test]
+        fn large_octave_range() {
+            let mut signal = Signal::new(Props {
+                notes: vec![Note {
+                    frequency_hz: const_(1.0),
+                    gate: const_(true),
+                }],
+                clock: const_(false),
+                order: Order::Down,
+                octave_range: const_(40.0),
+                gate_length_01: const_(0.5),
+                latch: false,
+                seed: Some(0),
+            });
+            signal.update_held(&SignalCtx {
+                sample_index: 0,
+                sample_rate: 44100,
+            });
+            assert_eq!(signal.next_frequency_hz(40), Some(2_f64.powi(39)));
+        }
+
+        #[test]
+        fn up_down_over_two_octaves() {
+            let notes = [150.0, 100.0]
+                .into_iter()
+                .map(|frequency_hz| Note {
+                    frequency_hz: const_(frequency_hz),
+                    gate: const_(true),
+                })
+                .collect();
+            let mut signal = Signal::new(Props {
+                notes,
+                clock: const_(false),
+                order: Order::UpDown,
+                octave_range: const_(2.0),
+                gate_length_01: const_(0.5),
+                latch: false,
+                seed: Some(0),
+            });
+            signal.update_held(&SignalCtx {
+                sample_index: 0,
+                sample_rate: 44100,
+            });
+            let frequencies_hz = (0..8)
+                .map(|_| signal.next_frequency_hz(2).unwrap())
+                .collect::<Vec<_>>();
+            assert_eq!(
+                frequencies_hz,
+                vec![100.0, 150.0, 200.0, 300.0, 200.0, 150.0, 100.0, 150.0]
+            );
+            // the scratch buffer never grows beyond its initial allocation
+            assert_eq!(signal.ordered_held.capacity(), 2);
+        }
+    }
+}
+
+pub mod trigger_sequencer_8 {
+    use crate::signal::*;
+
+    pub struct Props {
+        pub sequence: Vec<Su8>,
+        pub clock: Sbool,
+    }
+
+    struct Signal {
+        props: Props,
+        step_index: usize,
+    }
+
+    impl Signal {
+        fn new(props: Props) -> Self {
+            // the sequence will start on the first clock pulse
+            Self {
+                step_index: props.sequence.len() - 1,
+                props,
+            }
+        }
+    }
+
+    impl SignalTrait<u8> for Signal {
+        fn sample(&mut self, ctx: &SignalCtx) -> u8 {
+            if self.props.clock.sample(ctx) {
+                self.step_index = (self.step_index + 1) % self.props.sequence.len();
+                self.props.sequence[self.step_index].sample(ctx)
+            } else {
+                0
+            }
+        }
+    }
+
+    pub fn create(props: Props) -> [Sbool; 8] {
+        Su8::new(Signal::new(props)).expand()
+    }
+}
+
+pub mod rhythm {
+    pub mod euclidean {
+        use crate::signal::*;
+
+        pub struct Props {
+            pub clock: Sbool,
+            /// number of hits to distribute as evenly as possible among the steps
+            pub hits: Sf64,
+            pub steps: Sf64,
+            /// number of steps by which to rotate the pattern
+            pub rotation: Sf64,
+        }
+
+        struct Signal {
+            props: Props,
+            step_count: u64,
+        }
+
+        impl Signal {
+            fn new(props: Props) -> Self {
+                Self {
+                    props,
+                    step_count: 0,
+                }
+            }
+        }
+
+        impl SignalTrait<bool> for Signal {
+            fn sample(&mut self, ctx: &SignalCtx) -> bool {
+                let steps = self.props.steps.sample(ctx).round().max(1.0) as i64;
+                let hits = (self.props.hits.sample(ctx).round() as i64).clamp(0, steps);
+                let rotation = self.props.rotation.sample(ctx).round() as i64;
+                if self.props.clock.sample(ctx) {
+                    let step_index = (self.step_count as i64 + rotation).rem_euclid(steps);
+                    self.step_count += 1;
+                    (step_index * hits) % steps < hits
+                } else {
+                    false
+                }
+            }
+        }
+
+        pub fn create(props: Props) -> Sbool {
+            Sbool::new(Signal::new(props))
+        }
+    }
+
+    pub mod chance {
+        use super::super::random_uniform::rng;
+        use crate::signal::*;
+        use rand::Rng;
+        use rand_xorshift::XorShiftRng;
+
+        pub struct Props {
+            pub trigger: Sbool,
+            /// probability that each trigger is passed through
+            pub probability_01: Sf64,
+            pub seed: Option<u64>,
+        }
+
+        struct Signal {
+            props: Props,
+            rng: XorShiftRng,
+        }
+
+        impl Signal {
+            fn new(props: Props) -> Self {
+                Self {
+                    rng: rng(props.seed),
+                    props,
+                }
+            }
+        }
+
+        impl SignalTrait<bool> for Signal {
+            fn sample(&mut self, ctx: &SignalCtx) -> bool {
+                let probability_01 = self.props.probability_01.sample(ctx);
+                self.props.trigger.sample(ctx) && self.rng.gen::<f64>() < probability_01
+            }
+        }
+
+        pub fn create(props: Props) -> Sbool {
+            Sbool::new(Signal::new(props))
+        }
+    }
+
+    pub mod ratchet {
+        use crate::signal::*;
+
+        pub struct Props {
+            pub trigger: Sbool,
+            /// total number of triggers produced for each input trigger
+            pub count: Sf64,
+            pub interval_seconds: Sf64,
+        }
+
+        struct Signal {
+            props: Props,
+            remaining: u64,
+            next_remain_seconds: f64,
+        }
+
+        impl Signal {
+            fn new(props: Props) -> Self {
+                Self {
+                    props,
+                    remaining: 0,
+                    next_remain_seconds: 0.0,
+                }
+            }
+        }
+
+        impl SignalTrait<bool> for Signal {
+            fn sample(&mut self, ctx: &SignalCtx) -> bool {
+                let count = self.props.count.sample(ctx).round().max(1.0) as u64;
+                let interval_seconds = self.props.interval_seconds.sample(ctx);
+                if self.props.trigger.sample(ctx) {
+                    self.remaining = count - 1;
+                    self.next_remain_seconds = interval_seconds;
+                    return true;
+                }
+                if self.remaining == 0 {
+                    return false;
+                }
+                self.next_remain_seconds -= 1.0 / ctx.sample_rate as f64;
+                if self.next_remain_seconds <= 0.0 {
+                    self.remaining -= 1;
+                    self.next_remain_seconds += interval_seconds;
+                    true
+                } else {
+                    false
+                }
+            }
+        }
+
+        pub fn create(props: Props) -> Sbool {
+            Sbool::new(Signal::new(props))
+        }
+    }
+
+    pub mod turing_machine {
+        use super::super::random_uniform::rng;
+        use crate::signal::*;
+        use rand::Rng;
+        use rand_xorshift::XorShiftRng;
+
+        pub const MAX_LENGTH: u32 = 32;
+
+        pub struct Props {
+            pub clock: Sbool,
+            /// number of steps before the sequence repeats (up to `MAX_LENGTH`)
+            pub length: Sf64,
+            /// probability of changing each step as it's repeated, where 0 locks the sequence and
+            /// 1 inverts it each time around
+            pub flip_probability_01: Sf64,
+            pub seed: Option<u64>,
+        }
+
+        struct Signal {
+            props: Props,
+            rng: XorShiftRng,
+            register: u32,
+        }
+
+        impl Signal {
+            fn new(props: Props) -> Self {
+                let mut rng = rng(props.seed);
+                Self {
+                    register: rng.gen(),
+                    rng,
+                    props,
+                }
+            }
+        }
+
+        #[derive(Clone)]
+        struct OutputSample {
+            trigger: bool,
+            value_01: f64,
+        }
+
+        pub struct Output {
+            /// triggered on each clock pulse where the current step is set
+            pub trigger: Sbool,
+            /// the 8 most recent steps interpreted as a number
+            pub value_01: Sf64,
+        }
+
+        impl SignalTrait<OutputSample> for Signal {
+            fn sample(&mut self, ctx: &SignalCtx) -> OutputSample {
+                let length = (self.props.length.sample(ctx).round() as u32).clamp(1, MAX_LENGTH);
+                let flip_probability_01 = self.props.flip_probability_01.sample(ctx);
+                let trigger = if self.props.clock.sample(ctx) {
+                    // the step falling off the end of the sequence becomes the new first step
+                    let mut bit = (self.register >> (length - 1)) & 1;
+                    if self.rng.gen::<f64>() < flip_probability_01 {
+                        bit ^= 1;
+                    }
+                    self.register = (self.register << 1) | bit;
+                    bit == 1
+                } else {
+                    false
+                };
+                OutputSample {
+                    trigger,
+                    value_01: (self.register & 0xFF) as f64 / 255.0,
+                }
+            }
+        }
+
+        pub fn create(props: Props) -> Output {
+            let combined_signal = BufferedSignal::new(Signal::new(props));
+            Output {
+                trigger: combined_signal.map(|s| s.trigger),
+                value_01: combined_signal.map(|s| s.value_01),
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod test {
+        use super::*;
+        use crate::{
+            dsl::{const_, var},
+            signal::*,
+        };
+
+        // Samples a signal once per sample at a sample rate of 4Hz
+        fn samples<T: Clone + 'static>(signal: &mut BufferedSignal<T>, n: u64) -> Vec<T> {
+            (0..n)
+                .map(|sample_index| {
+                    signal.sample(&SignalCtx {
+                        sample_index,
+                        sample_rate: 4,
+                    })
+                })
+                .collect()
+        }
+
+        fn pattern(triggers: &[bool]) -> String {
+            triggers
+                .iter()
+                .map(|&trigger| if trigger { 'x' } else { '.' })
+                .collect()
+        }
+
+        fn euclidean(hits: f64, steps: f64, rotation: f64) -> String {
+            let mut signal = euclidean::create(euclidean::Props {
+                clock: const_(true),
+                hits: const_(hits),
+                steps: const_(steps),
+                rotation: const_(rotation),
+            });
+            pattern(&samples(&mut signal, steps as u64))
+        }
+
+        #[test]
+        fn euclidean_patterns() {
+            assert_eq!(euclidean(3.0, 8.0, 0.0), "x..x..x.");
+            assert_eq!(euclidean(3.0, 8.0, 1.0), "..x..x.x");
+            assert_eq!(euclidean(3.0, 8.0, -1.0), ".x..x..x");
+            assert_eq!(euclidean(5.0, 8.0, 0.0), "x.x.xx.x");
+            assert_eq!(euclidean(0.0, 4.0, 0.0), "....");
+            assert_eq!(euclidean(10.0, 4.0, 0.0), "xxxx");
+        }
+
+        fn chance(probability_01: f64, seed: u64) -> Vec<bool> {
+            let mut signal = chance::create(chance::Props {
+                trigger: const_(true),
+                probability_01: const_(probability_01),
+                seed: Some(seed),
+            });
+            samples(&mut signal, 64)
+        }
+
+        #[test]
+        fn chance_is_deterministic_when_seeded() {
+            assert_eq!(chance(0.5, 1), chance(0.5, 1));
+            assert_ne!(chance(0.5, 1), chance(0.5, 2));
+            assert!(chance(0.5, 1).contains(&true));
+            assert!(chance(0.5, 1).contains(&false));
+            assert!(chance(0.0, 1).iter().all(|&trigger| !trigger));
+            assert!(chance(1.0, 1).iter().all(|&trigger| trigger));
+        }
+
+        #[test]
+        fn ratchet_repeats_triggers() {
+            let (trigger, trigger_var) = var(false);
+            let mut signal = ratchet::create(ratchet::Props {
+                trigger,
+                count: const_(3.0),
+                interval_seconds: const_(0.5),
+            });
+            let triggers = (0..8)
+                .map(|sample_index| {
+                    trigger_var.set(sample_index == 0);
+                    signal.sample(&SignalCtx {
+                        sample_index,
+                        sample_rate: 4,
+                    })
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(pattern(&triggers), "x.x.x...");
+        }
+
+        fn turing_machine(length: f64, flip_probability_01: f64, seed: u64) -> Vec<bool> {
+            let mut signal = turing_machine::create(turing_machine::Props {
+                clock: const_(true),
+                length: const_(length),
+                flip_probability_01: const_(flip_probability_01),
+                seed: Some(seed),
+            })
+            .trigger;
+            samples(&mut signal, 64)
+        }
+
+        #[test]
+        fn turing_machine_is_deterministic_when_seeded() {
+            assert_eq!(turing_machine(16.0, 0.5, 1), turing_machine(16.0, 0.5, 1));
+            assert_ne!(turing_machine(16.0, 0.5, 1), turing_machine(16.0, 0.5, 2));
+        }
+
+        #[test]
+        fn turing_machine_flip_probability() {
+            // a locked sequence repeats, and a sequence that always flips is inverted each time
+            let locked = turing_machine(5.0, 0.0, 1);
+            assert!((5..locked.len()).all(|i| locked[i] == locked[i - 5]));
+            let flipped = turing_machine(5.0, 1.0, 1);
+            assert!((5..flipped.len()).all(|i| flipped[i] != flipped[i - 5]));
+        }
+    }
+}
+
+pub mod sample_player {
+    use crate::{sample::Sample, signal::*};
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum LoopMode {
+        /// play until the end of the sample and then stop
+        OneShot,
+        /// jump back to the start of the loop upon reaching its end
+        Forward,
+        /// reverse direction upon reaching either end of the loop
+        PingPong,
+    }
+
+    pub struct Props {
+        pub sample: Sample,
+        pub trigger: Sbool,
+        /// 1 plays the sample at its original pitch, 2 plays it an octave higher, etc
+        pub playback_rate: Sf64,
+        pub loop_mode: LoopMode,
+        /// index of the first frame of the loop
+        pub loop_start: usize,
+        /// index of the frame after the end of the loop, defaulting to the end of the sample
+        pub loop_end: Option<usize>,
+        /// play from the end of the sample towards the start
+        pub reverse: bool,
+        /// number of frames to skip when the sample is triggered (from the end if reversed)
+        pub start_offset: usize,
+    }
+
+    impl Props {
+        /// Plays the entire sample once at its original pitch when triggered
+        pub fn new(sample: Sample, trigger: Sbool) -> Self {
+            Self {
+                sample,
+                trigger,
+                playback_rate: Const::new(1.0).buffered_signal(),
+                loop_mode: LoopMode::OneShot,
+                loop_start: 0,
+                loop_end: None,
+                reverse: false,
+                start_offset: 0,
+            }
+        }
+    }
+
+    struct Signal {
+        props: Props,
+        // position in the sample in frames, or `None` if the sample isn't playing
+        position: Option<f64>,
+        direction: f64,
+    }
+
+    impl Signal {
+        fn new(props: Props) -> Self {
+            Self {
+                props,
+                position: None,
+                direction: 1.0,
+            }
+        }
+
+        fn start_position(&self) -> f64 {
+            let offset = self.props.start_offset as f64;
+            if self.props.reverse {
+                (self.props.sample.len() as f64 - 1.0 - offset).max(0.0)
+            } else {
+                offset
+            }
+        }
+
+        fn next_position(&mut self, position: f64, step: f64) -> Option<f64> {
+            let len = self.props.sample.len() as f64;
+            let loop_end = self.props.loop_end.map_or(len, |x| (x as f64).min(len));
+            let loop_start = (self.props.loop_start as f64).min(loop_end);
+            let loop_len = loop_end - loop_start;
+            let next = position + step;
+            let loop_mode = if loop_len > 0.0 {
+                self.props.loop_mode
+            } else {
+                LoopMode::OneShot
+            };
+            match loop_mode {
+                LoopMode::OneShot => {
+                    if next < 0.0 || next >= len {
+                        None
+                    } else {
+                        Some(next)
+                    }
+                }
+                LoopMode::Forward => {
+                    if step > 0.0 && next >= loop_end {
+                        Some(loop_start + (next - loop_end).rem_euclid(loop_len))
+                    } else if step < 0.0 && next < loop_start {
+                        Some(loop_end - (loop_start - next).rem_euclid(loop_len))
+                    } else {
+                        Some(next)
+                    }
+                }
+                LoopMode::PingPong => {
+                    if step > 0.0 && next >= loop_end {
+                        self.direction = -self.direction;
+                        Some((loop_end - (next - loop_end)).max(loop_start))
+                    } else if step < 0.0 && next < loop_start {
+                        self.direction = -self.direction;
+                        Some((loop_start + (loop_start - next)).min(loop_end))
+                    } else {
+                        Some(next)
+                    }
+                }
+            }
+        }
+    }
+
+    impl SignalTrait<f64> for Signal {
+        fn sample(&mut self, ctx: &SignalCtx) -> f64 {
+            if self.props.trigger.sample(ctx) {
+                self.position = Some(self.start_position());
+                self.direction = if self.props.reverse { -1.0 } else { 1.0 };
+            }
+            let playback_rate = self.props.playback_rate.sample(ctx);
+            if let Some(position) = self.position {
+                let output = self.props.sample.interpolate(position);
+                let step = playback_rate
+                    * self.direction
+                    * (self.props.sample.sample_rate_hz() as f64 / ctx.sample_rate as f64);
+                self.position = self.next_position(position, step);
+                output
+            } else {
+                0.0
+            }
+        }
+    }
+
+    pub fn create(props: Props) -> Sf64 {
+        Sf64::new(Signal::new(props))
+    }
+}
+
+pub mod slicer {
+    use crate::{sample::Sample, signal::*};
+
+    // Duration of the fades applied to the start and end of each slice to avoid clicks
+    const FADE_SECONDS: f64 = 0.002;
+
+    pub enum Slices {
+        /// split the sample into this many slices of equal length
+        Equal(usize),
+        /// start a new slice wherever the level of the sample rises by at least this many decibels
+        Transients { rise_db: f64 },
+        /// start a new slice at each of these frame indices
+        Starts(Vec<usize>),
+    }
+
+    pub struct Props {
+        pub sample: Sample,
+        pub trigger: Sbool,
+        /// index of the slice to play when triggered, wrapping around the number of slices
+        pub index: Sf64,
+        pub slices: Slices,
+        /// 1 plays the sample at its original pitch, 2 plays it an octave higher, etc
+        pub playback_rate: Sf64,
+    }
+
+    struct Signal {
+        props: Props,
+        // pairs of start and end frame indices
+        slices: Vec<(usize, usize)>,
+        // position in the sample in frames and the index of the slice being played, or `None` if
+        // no slice is playing
+        playing: Option<(f64, usize)>,
+    }
+
+    impl Signal {
+        fn new(props: Props) -> Self {
+            let len = props.sample.len();
+            let mut starts = match &props.slices {
+                Slices::Equal(count) => {
+                    let count = (*count).max(1);
+                    (0..count).map(|i| (i * len) / count).collect()
+                }
+                Slices::Transients { rise_db } => props.sample.detect_transients(*rise_db),
+                Slices::Starts(starts) => starts.iter().map(|&x| x.min(len)).collect::<Vec<_>>(),
+            };
+            starts.sort_unstable();
+            starts.dedup();
+            let slices = starts
+                .iter()
+                .enumerate()
+                .map(|(i, &start)| (start, starts.get(i + 1).cloned().unwrap_or(len)))
+                .filter(|(start, end)| start < end)
+                .collect();
+            Self {
+                props,
+                slices,
+                playing: None,
+            }
+        }
+    }
+
+    impl SignalTrait<f64> for Signal {
+        fn sample(&mut self, ctx: &SignalCtx) -> f64 {
+            let index = self.props.index.sample(ctx);
+            let playback_rate = self.props.playback_rate.sample(ctx);
+            if self.props.trigger.sample(ctx) && !self.slices.is_empty() {
+                let slice_index = (index.floor() as i64).rem_euclid(self.slices.len() as i64);
+                let slice_index = slice_index as usize;
+                self.playing = Some((self.slices[slice_index].0 as f64, slice_index));
+            }
+            if let Some((position, slice_index)) = self.playing {
+                let (start, end) = self.slices[slice_index];
+                let fade_len = FADE_SECONDS * self.props.sample.sample_rate_hz() as f64;
+                let fade = ((position - start as f64) / fade_len)
+                    .min((end as f64 - position) / fade_len)
+                    .clamp(0.0, 1.0);
+                let output = self.props.sample.interpolate(position) * fade;
+                let next = position
+                    + (playback_rate
+                        * (self.props.sample.sample_rate_hz() as f64 / ctx.sample_rate as f64));
+                self.playing = if next < start as f64 || next >= end as f64 {
+                    None
+                } else {
+                    Some((next, slice_index))
+                };
+                output
+            } else {
+                0.0
+            }
+        }
+    }
+
+    pub fn create(props: Props) -> Sf64 {
+        Sf64::new(Signal::new(props))
+    }
+}
+
+pub mod distortion {
+    use super::biquad_filter::ButterworthLowPass;
+
+    const ANTI_ALIASING_FILTER_ORDER_HALF: usize = 2;
+
+    // Fraction of the oversampled rate at which to put the cutoff of the anti-aliasing filter.
+    // This is just below the nyquist frequency of the output.
+    const ANTI_ALIASING_CUTOFF_RATIO: f64 = 0.45;
+
+    // Runs a nonlinear function at a multiple of the sample rate, upsampling the input with
+    // linear interpolation and removing harmonics above the output's nyquist frequency with a
+    // butterworth low pass filter before decimating. The function is expected to mix in the
+    // unaffected signal itself, so that the unaffected signal is delayed by the filter by the
+    // same amount as the affected signal and the two don't interfere when mixed.
+    struct Oversampler {
+        factor: usize,
+        prev_input: f64,
+        filter: ButterworthLowPass,
+    }
+
+    impl Oversampler {
+        fn new(factor: usize) -> Self {
+            let factor = factor.max(1);
+            Self {
+                factor,
+                prev_input: 0.0,
+                filter: ButterworthLowPass::new(
+                    ANTI_ALIASING_FILTER_ORDER_HALF,
+                    ANTI_ALIASING_CUTOFF_RATIO / factor as f64,
+                ),
+            }
+        }
+
+        fn process<F: FnMut(f64) -> f64>(&mut self, input: f64, mut f: F) -> f64 {
+            if self.factor == 1 {
+                return f(input);
+            }
+            let mut output = 0.0;
+            for i in 1..=self.factor {
+                let t = i as f64 / self.factor as f64;
+                let x = self.prev_input + ((input - self.prev_input) * t);
+                output = self.filter.apply(f(x));
+            }
+            self.prev_input = input;
+            output
+        }
+    }
+
+    pub mod waveshaper {
+        use super::*;
+        use crate::signal::*;
+
+        const TUBE_BIAS: f64 = 0.2;
+
+        pub enum Curve {
+            /// Smooth saturation which approaches +/-1
+            Tanh,
+            /// Cubic soft clipper which reaches +/-1 when the input reaches +/-1
+            SoftClip,
+            /// Asymmetric saturation which clips positive and negative halves differently, adding
+            /// even harmonics
+            Tube,
+            /// Reflects the signal back into the range -1 to 1 each time it exceeds it
+            Foldback,
+            /// Transfer curve sampled at evenly spaced inputs from -1 to 1 (inclusive). Inputs
+            /// between points are linearly interpolated and inputs outside the range are clamped.
+            Table(Vec<f64>),
+        }
+
+        impl Curve {
+            fn apply(&self, x: f64) -> f64 {
+                match self {
+                    Self::Tanh => x.tanh(),
+                    Self::SoftClip => {
+                        let x = x.clamp(-1.0, 1.0);
+                        1.5 * (x - ((x * x * x) / 3.0))
+                    }
+                    Self::Tube => (x + TUBE_BIAS).tanh() - TUBE_BIAS.tanh(),
+                    Self::Foldback => 1.0 - ((x + 1.0).rem_euclid(4.0) - 2.0).abs(),
+                    Self::Table(table) => match table.len() {
+                        0 => 0.0,
+                        1 => table[0],
+                        len => {
+                            let position = ((x.clamp(-1.0, 1.0) + 1.0) / 2.0) * (len - 1) as f64;
+                            let index = (position.floor() as usize).min(len - 2);
+                            let t = position - index as f64;
+                            table[index] + ((table[index + 1] - table[index]) * t)
+                        }
+                    },
+                }
+            }
+        }
+
+        pub struct Props {
+            pub signal: Sf64,
+            pub curve: Curve,
+            /// multiplied by the signal before applying the curve
+            pub drive: Sf64,
+            /// proportion of the shaped signal in the output (the rest is the unaffected signal)
+            pub mix_01: Sf64,
+            /// factor by which to increase the sample rate while applying the curve
+            pub oversample: usize,
+        }
+
+        struct Signal {
+            oversampler: Oversampler,
+            props: Props,
+        }
+
+        impl Signal {
+            fn new(props: Props) -> Self {
+                Self {
+                    oversampler: Oversampler::new(props.oversample),
+                    props,
+                }
+            }
+        }
+
+        impl SignalTrait<f64> for Signal {
+            fn sample(&mut self, ctx: &SignalCtx) -> f64 {
+                let dry = self.props.signal.sample(ctx);
+                let drive = self.props.drive.sample(ctx);
+                let mix_01 = self.props.mix_01.sample(ctx);
+                let curve = &self.props.curve;
+                self.oversampler.process(dry, |x| {
+                    let wet = curve.apply(x * drive);
+                    x + ((wet - x) * mix_01)
+                })
+            }
+        }
+
+        pub fn create(props: Props) -> Sf64 {
+            Sf64::new(Signal::new(props))
+        }
+    }
+
+    pub mod bitcrusher {
+        use super::*;
+        use crate::signal::*;
+
+        const MIN_BITS: f64 = 1.0;
+
+        pub struct Props {
+            pub signal: Sf64,
+            /// resolution of the output (need not be an integer)
+            pub bits: Sf64,
+            /// rate at which the input is sampled and held
+            pub sample_rate_hz: Sf64,
+            /// proportion of the crushed signal in the output (the rest is the unaffected signal)
+            pub mix_01: Sf64,
+            /// factor by which to increase the sample rate while crushing the signal
+            pub oversample: usize,
+        }
+
+        struct Signal {
+            oversampler: Oversampler,
+            props: Props,
+            phase: f64,
+            held_sample: f64,
+        }
+
+        impl Signal {
+            fn new(props: Props) -> Self {
+                Self {
+                    oversampler: Oversampler::new(props.oversample),
+                    phase: 1.0,
+                    held_sample: 0.0,
+                    props,
+                }
+            }
+        }
+
+        impl SignalTrait<f64> for Signal {
+            fn sample(&mut self, ctx: &SignalCtx) -> f64 {
+                let dry = self.props.signal.sample(ctx);
+                let levels = 2_f64.powf(self.props.bits.sample(ctx).max(MIN_BITS) - 1.0);
+                let phase_step = self.props.sample_rate_hz.sample(ctx)
+                    / (ctx.sample_rate as f64 * self.oversampler.factor as f64);
+                let mix_01 = self.props.mix_01.sample(ctx);
+                let phase = &mut self.phase;
+                let held_sample = &mut self.held_sample;
+                self.oversampler.process(dry, |x| {
+                    *phase += phase_step;
+                    if *phase >= 1.0 {
+                        *phase = phase.rem_euclid(1.0);
+                        *held_sample = (x * levels).round() / levels;
+                    }
+                    x + ((*held_sample - x) * mix_01)
+                })
+            }
+        }
+
+        pub fn create(props: Props) -> Sf64 {
+            Sf64::new(Signal::new(props))
+        }
+    }
+
+    #[cfg(test)]
+    mod test {
+        use super::*;
+        use crate::{
+            dsl::{const_, var},
+            signal::*,
+        };
+
+        // A curve which doesn't change the signal should produce the same output regardless of
+        // the mix, as the unaffected signal is delayed by the same amount as the affected signal
+        #[test]
+        fn mix_is_in_phase() {
+            let outputs = [0.5, 1.0].map(|mix_01| {
+                let (signal, signal_var) = var(0.0);
+                let mut shaped = waveshaper::create(waveshaper::Props {
+                    signal,
+                    curve: waveshaper::Curve::Table(vec![-1.0, 1.0]),
+                    drive: const_(1.0),
+                    mix_01: const_(mix_01),
+                    oversample: 4,
+                });
+                (0..100)
+                    .map(|sample_index| {
+                        signal_var.set((sample_index as f64 * 0.3).sin());
+                        shaped.sample(&SignalCtx {
+                            sample_index,
+                            sample_rate: 44100,
+                        })
+                    })
+                    .collect::<Vec<_>>()
+            });
+            for (a, b) in outputs[0].iter().zip(outputs[1].iter()) {
+                assert!((a - b).abs() < 1e-12);
+            }
+        }
+    }
+}
+
+pub mod dynamics {
+    // Level below which signals are considered silent to avoid taking the log of 0
+    const MIN_DB: f64 = -120.0;
+
+    fn ratio_to_db(ratio: f64) -> f64 {
+        if ratio > 0.0 {
+            (20.0 * ratio.log10()).max(MIN_DB)
+        } else {
+            MIN_DB
+        }
+    }
+
+    fn db_to_ratio(db: f64) -> f64 {
+        10_f64.powf(db / 20.0)
+    }
+
+    // Coefficient of a one-pole smoothing filter which covers about 63% of the distance to its
+    // target in the given time.
+    pub(super) fn smoothing_coefficient(seconds: f64, sample_rate: u32) -> f64 {
+        if seconds <= 0.0 {
+            0.0
+        } else {
+            (-1.0 / (seconds * sample_rate as f64)).exp()
+        }
+    }
+
+    pub mod compressor {
+        use super::*;
+        use crate::signal::*;
+
+        pub struct Props {
+            pub signal: Sf64,
+            /// the level of this signal determines how much to compress `signal`
+            pub sidechain: Sf64,
+            pub threshold_db: Sf64,
+            pub ratio: Sf64,
+            pub attack_seconds: Sf64,
+            pub release_seconds: Sf64,
+            /// width of the region around the threshold where the ratio is gradually applied
+            pub knee_db: Sf64,
+            pub makeup_gain_db: Sf64,
+        }
+
+        struct Signal {
+            props: Props,
+            gain_reduction_db: f64,
+        }
+
+        impl Signal {
+            fn new(props: Props) -> Self {
+                Self {
+                    props,
+                    gain_reduction_db: 0.0,
+                }
+            }
+        }
+
+        fn target_gain_reduction_db(
+            level_db: f64,
+            threshold_db: f64,
+            ratio: f64,
+            knee_db: f64,
+        ) -> f64 {
+            let over_db = level_db - threshold_db;
+            let slope = 1.0 - (1.0 / ratio);
+            if 2.0 * over_db < -knee_db {
+                0.0
+            } else if 2.0 * over_db.abs() <= knee_db {
+                let x = over_db + (knee_db / 2.0);
+                (slope * x * x) / (2.0 * knee_db)
+            } else {
+                slope * over_db
+            }
+        }
+
+        impl SignalTrait<f64> for Signal {
+            fn sample(&mut self, ctx: &SignalCtx) -> f64 {
+                let sample = self.props.signal.sample(ctx);
+                let level_db = ratio_to_db(self.props.sidechain.sample(ctx).abs());
+                let target_db = target_gain_reduction_db(
+                    level_db,
+                    self.props.threshold_db.sample(ctx),
+                    self.props.ratio.sample(ctx).max(1.0),
+                    self.props.knee_db.sample(ctx).max(0.0),
+                );
+                let seconds = if target_db > self.gain_reduction_db {
+                    self.props.attack_seconds.sample(ctx)
+                } else {
+                    self.props.release_seconds.sample(ctx)
+                };
+                let coefficient = smoothing_coefficient(seconds, ctx.sample_rate);
+                self.gain_reduction_db =
+                    target_db + ((self.gain_reduction_db - target_db) * coefficient);
+                let gain_db = self.props.makeup_gain_db.sample(ctx) - self.gain_reduction_db;
+                sample * db_to_ratio(gain_db)
+            }
+        }
+
+        pub fn create(props: Props) -> Sf64 {
+            Sf64::new(Signal::new(props))
+        }
+    }
+
+    pub mod limiter {
+        use super::*;
+        use crate::signal::*;
+        use std::collections::VecDeque;
+
+        // The gain is reduced over this fraction of the lookahead time, leaving the remainder for
+        // the gain to settle before a peak is played.
+        const ATTACK_LOOKAHEAD_RATIO: f64 = 0.25;
+
+        pub struct Props {
+            pub signal: Sf64,
+            /// the output will never exceed this level
+            pub ceiling_db: Sf64,
+            /// the output is delayed by this amount so the gain can be reduced before peaks arrive
+            pub lookahead_seconds: f64,
+            pub release_seconds: Sf64,
+        }
+
+        struct Signal {
+            props: Props,
+            delay: VecDeque<f64>,
+            // (sample_index, gain) pairs of the gains required to keep each sample in the
+            // lookahead window below the ceiling, kept in increasing order of gain so the front
+            // is always the minimum gain in the window
+            required_gains: VecDeque<(u64, f64)>,
+            gain: f64,
+        }
+
+        impl Signal {
+            fn new(props: Props) -> Self {
+                Self {
+                    props,
+                    delay: VecDeque::new(),
+                    required_gains: VecDeque::new(),
+                    gain: 1.0,
+                }
+            }
+        }
+
+        impl SignalTrait<f64> for Signal {
+            fn sample(&mut self, ctx: &SignalCtx) -> f64 {
+                let sample = self.props.signal.sample(ctx);
+                let ceiling = db_to_ratio(self.props.ceiling_db.sample(ctx));
+                let lookahead_samples =
+                    ((self.props.lookahead_seconds * ctx.sample_rate as f64).round() as u64).max(1);
+                let required_gain = if sample.abs() > ceiling {
+                    ceiling / sample.abs()
+                } else {
+                    1.0
+                };
+                while let Some(&(_, gain)) = self.required_gains.back() {
+                    if gain < required_gain {
+                        break;
+                    }
+                    self.required_gains.pop_back();
+                }
+                self.required_gains
+                    .push_back((ctx.sample_index, required_gain));
+                while let Some(&(sample_index, _)) = self.required_gains.front() {
+                    if sample_index + lookahead_samples >= ctx.sample_index {
+                        break;
+                    }
+                    self.required_gains.pop_front();
+                }
+                let target_gain = self.required_gains.front().map_or(1.0, |&(_, gain)| gain);
+                let seconds = if target_gain < self.gain {
+                    (self.props.lookahead_seconds * ATTACK_LOOKAHEAD_RATIO) / 4.0
+                } else {
+                    self.props.release_seconds.sample(ctx)
+                };
+                let coefficient = smoothing_coefficient(seconds, ctx.sample_rate);
+                self.gain = target_gain + ((self.gain - target_gain) * coefficient);
+                self.delay.push_back(sample);
+                let delayed_sample = if self.delay.len() as u64 > lookahead_samples {
+                    self.delay.pop_front().unwrap_or(0.0)
+                } else {
+                    0.0
+                };
+                (delayed_sample * self.gain).clamp(-ceiling, ceiling)
+            }
+        }
+
+        pub fn create(props: Props) -> Sf64 {
+            Sf64::new(Signal::new(props))
+        }
+    }
+
+    pub mod noise_gate {
+        use super::*;
+        use crate::signal::*;
+
+        const DETECTOR_RELEASE_SECONDS: f64 = 0.01;
+
+        pub struct Props {
+            pub signal: Sf64,
+            /// the gate opens when the signal's level exceeds this
+            pub threshold_db: Sf64,
+            pub attack_seconds: Sf64,
+            pub release_seconds: Sf64,
+        }
+
+        struct Signal {
+            props: Props,
+            level: f64,
+            gain: f64,
+        }
+
+        impl Signal {
+            fn new(props: Props) -> Self {
+                Self {
+                    props,
+                    level: 0.0,
+                    gain: 0.0,
+                }
+            }
+        }
+
+        impl SignalTrait<f64> for Signal {
+            fn sample(&mut self, ctx: &SignalCtx) -> f64 {
+                let sample = self.props.signal.sample(ctx);
+                self.level = sample.abs().max(
+                    self.level * smoothing_coefficient(DETECTOR_RELEASE_SECONDS, ctx.sample_rate),
+                );
+                let delta = if ratio_to_db(self.level) > self.props.threshold_db.sample(ctx) {
+                    1.0 / (self.props.attack_seconds.sample(ctx) * ctx.sample_rate as f64)
+                } else {
+                    -1.0 / (self.props.release_seconds.sample(ctx) * ctx.sample_rate as f64)
+                };
+                self.gain = (self.gain + delta).clamp(0.0, 1.0);
+                sample * self.gain
+            }
+        }
+
+        pub fn create(props: Props) -> Sf64 {
+            Sf64::new(Signal::new(props))
+        }
+    }
+}
+
+pub mod envelope_follower {
+    use super::dynamics::smoothing_coefficient;
+    use crate::signal::*;
+
+    #[derive(Debug, Clone, Copy)]
+    pub enum Mode {
+        /// follows the absolute value of the signal
+        Peak,
+        /// follows the root mean square of the signal
+        Rms,
+    }
+
+    pub struct Props {
+        pub signal: Sf64,
+        pub mode: Mode,
+        pub attack_seconds: Sf64,
+        pub release_seconds: Sf64,
+    }
+
+    struct Signal {
+        props: Props,
+        state: f64,
+    }
+
+    impl Signal {
+        fn new(props: Props) -> Self {
+            Self { props, state: 0.0 }
+        }
+    }
+
+    impl SignalTrait<f64> for Signal {
+        fn sample(&mut self, ctx: &SignalCtx) -> f64 {
+            let sample = self.props.signal.sample(ctx);
+            let input = match self.props.mode {
+                Mode::Peak => sample.abs(),
+                Mode::Rms => sample * sample,
+            };
+            let seconds = if input > self.state {
+                self.props.attack_seconds.sample(ctx)
+            } else {
+                self.props.release_seconds.sample(ctx)
+            };
+            let coefficient = smoothing_coefficient(seconds, ctx.sample_rate);
+            self.state = input + ((self.state - input) * coefficient);
+            match self.props.mode {
+                Mode::Peak => self.state,
+                Mode::Rms => self.state.sqrt(),
+            }
+        }
+    }
+
+    pub fn create(props: Props) -> Sf64 {
+        Sf64::new(Signal::new(props))
+    }
+}
+
+pub mod plucked_string {
+    // This is based on the extended Karplus-Strong algorithm described in:
+    // https://ccrma.stanford.edu/~jos/pasp/Extended_Karplus_Strong_Algorithm.html
+
+    use crate::signal::*;
+    use rand::{Rng, SeedableRng};
+    use rand_xorshift::XorShiftRng;
+
+    // Determines the size of the delay line. Lower frequencies will be clamped to this value.
+    const MIN_FREQUENCY_HZ: f64 = 20.0;
+
+    // Keep the fractional delay of the allpass interpolator away from 0 where it becomes
+    // unstable.
+    const MIN_ALLPASS_DELAY: f64 = 0.1;
+
+    // Shorter (or negative) decay times are clamped to this value so that the loop gain stays
+    // below 1.
+    const MIN_DECAY_SECONDS: f64 = 0.001;
+
+    pub enum Excitation {
+        /// A burst of white noise lasting a single period of the string
+        Noise,
+        /// This signal is fed into the string continuously. For example a sample player triggered
+        /// at the same time as the string. The string's trigger is ignored in this case.
+        Signal(Sf64),
+    }
+
+    pub struct Props {
+        pub frequency_hz: Sf64,
+        /// starts a burst of noise when the excitation is `Excitation::Noise`
+        pub trigger: Sbool,
+        pub excitation: Excitation,
+        /// time taken for the string to decay by 60dB
+        pub decay_seconds: Sf64,
+        /// 0 dampens high frequencies the most and 1 doesn't dampen them at all
+        pub brightness_01: Sf64,
+    }
+
+    struct Signal {
+        props: Props,
+        rng: XorShiftRng,
+        delay_line: Vec<f64>,
+        write_index: usize,
+        burst_remaining_samples: usize,
+        allpass_prev_input: f64,
+        allpass_prev_output: f64,
+        filter_prev_input: f64,
+    }
+
+    impl Signal {
+        fn new(props: Props) -> Self {
+            Self {
+                props,
+                rng: XorShiftRng::from_entropy(),
+                delay_line: Vec::new(),
+                write_index: 0,
+                burst_remaining_samples: 0,
+                allpass_prev_input: 0.0,
+                allpass_prev_output: 0.0,
+                filter_prev_input: 0.0,
+            }
+        }
+
+        fn excitation(&mut self, period_samples: f64, ctx: &SignalCtx) -> f64 {
+            let trigger = self.props.trigger.sample(ctx);
+            match self.props.excitation {
+                Excitation::Noise => {
+                    if trigger {
+                        self.burst_remaining_samples = period_samples.round() as usize;
+                    }
+                    if self.burst_remaining_samples > 0 {
+                        self.burst_remaining_samples -= 1;
+                        (self.rng.gen::<f64>() * 2.0) - 1.0
+                    } else {
+                        0.0
+                    }
+                }
+                Excitation::Signal(ref mut signal) => signal.sample(ctx),
+            }
+        }
+    }
+
+    impl SignalTrait<f64> for Signal {
+        fn sample(&mut self, ctx: &SignalCtx) -> f64 {
+            let sample_rate = ctx.sample_rate as f64;
+            if self.delay_line.is_empty() {
+                let len = (sample_rate / MIN_FREQUENCY_HZ).ceil() as usize + 2;
+                self.delay_line = vec![0.0; len];
+            }
+            let frequency_hz = self
+                .props
+                .frequency_hz
+                .sample(ctx)
+                .clamp(MIN_FREQUENCY_HZ, sample_rate / 2.0);
+            let period_samples = sample_rate / frequency_hz;
+            let input = self.excitation(period_samples, ctx);
+            // The damping filter is a weighted average of the current and previous sample, which
+            // delays the signal by `filter_weight` samples.
+            let filter_weight = 0.5 * (1.0 - self.props.brightness_01.sample(ctx).clamp(0.0, 1.0));
+            let delay_samples = period_samples - filter_weight;
+            let integer_delay = (delay_samples - MIN_ALLPASS_DELAY).floor().max(1.0);
+            let fractional_delay = delay_samples - integer_delay;
+            let allpass_coefficient = (1.0 - fractional_delay) / (1.0 + fractional_delay);
+            let len = self.delay_line.len();
+            let read_index = (self.write_index + len - integer_delay as usize) % len;
+            let delayed = self.delay_line[read_index];
+            let allpass_output = (allpass_coefficient * (delayed - self.allpass_prev_output))
+                + self.allpass_prev_input;
+            self.allpass_prev_input = delayed;
+            self.allpass_prev_output = allpass_output;
+            let filter_output =
+                ((1.0 - filter_weight) * allpass_output) + (filter_weight * self.filter_prev_input);
+            self.filter_prev_input = allpass_output;
+            let decay_seconds = self.props.decay_seconds.sample(ctx).max(MIN_DECAY_SECONDS);
+            let loop_gain = 0.001_f64.powf(1.0 / (frequency_hz * decay_seconds));
+            let output = input + (loop_gain * filter_output);
+            self.delay_line[self.write_index] = output;
+            self.write_index = (self.write_index + 1) % len;
+            output
+        }
+    }
+
+    pub fn create(props: Props) -> Sf64 {
+        Sf64::new(Signal::new(props))
+    }
+}
+
+pub mod drum {
+    use super::biquad_filter::Biquad;
+    use rand::{Rng, SeedableRng};
+    use rand_xorshift::XorShiftRng;
+    use std::f64::consts::PI;
+
+    // Shorter (or negative) decay times are clamped to this value so that envelopes always decay
+    const MIN_DECAY_SECONDS: f64 = 0.001;
+
+    // Exponential envelope which jumps to 1 when triggered and then decays by 60dB over the
+    // given time
+    struct Decay {
+        value: f64,
+    }
+
+    impl Decay {
+        fn new() -> Self {
+            Self { value: 0.0 }
+        }
+
+        fn sample(&mut self, trigger: bool, decay_seconds: f64, sample_rate: u32) -> f64 {
+            if trigger {
+                self.value = 1.0;
+            } else {
+                let decay_seconds = decay_seconds.max(MIN_DECAY_SECONDS);
+                self.value *= 0.001_f64.powf(1.0 / (decay_seconds * sample_rate as f64));
+            }
+            self.value
+        }
+    }
+
+    fn white_noise(rng: &mut XorShiftRng) -> f64 {
+        (rng.gen::<f64>() * 2.0) - 1.0
+    }
+
+    fn new_rng() -> XorShiftRng {
+        XorShiftRng::from_entropy()
+    }
+
+    pub mod kick {
+        use super::*;
+        use crate::signal::*;
+
+        const SWEEP_DECAY_SECONDS: f64 = 0.1;
+
+        pub struct Props {
+            pub trigger: Sbool,
+            /// frequency of the drum once the sweep has finished
+            pub frequency_hz: Sf64,
+            /// amount added to the frequency at the moment the drum is triggered
+            pub sweep_hz: Sf64,
+            pub decay_seconds: Sf64,
+        }
+
+        struct Signal {
+            props: Props,
+            amplitude: Decay,
+            sweep: Decay,
+            phase_01: f64,
+        }
+
+        impl Signal {
+            fn new(props: Props) -> Self {
+                Self {
+                    props,
+                    amplitude: Decay::new(),
+                    sweep: Decay::new(),
+                    phase_01: 0.0,
+                }
+            }
+        }
+
+        impl SignalTrait<f64> for Signal {
+            fn sample(&mut self, ctx: &SignalCtx) -> f64 {
+                let trigger = self.props.trigger.sample(ctx);
+                if trigger {
+                    self.phase_01 = 0.0;
+                }
+                let amplitude = self.amplitude.sample(
+                    trigger,
+                    self.props.decay_seconds.sample(ctx),
+                    ctx.sample_rate,
+                );
+                let sweep = self
+                    .sweep
+                    .sample(trigger, SWEEP_DECAY_SECONDS, ctx.sample_rate);
+                let frequency_hz =
+                    self.props.frequency_hz.sample(ctx) + (self.props.sweep_hz.sample(ctx) * sweep);
+                self.phase_01 =
+                    (self.phase_01 + (frequency_hz / ctx.sample_rate as f64)).rem_euclid(1.0);
+                (self.phase_01 * 2.0 * PI).sin() * amplitude
+            }
+        }
+
+        pub fn create(props: Props) -> Sf64 {
+            Sf64::new(Signal::new(props))
+        }
+    }
+
+    pub mod snare {
+        use super::*;
+        use crate::signal::*;
+
+        // ratio between the frequencies of the two sine waves making up the tone
+        const OVERTONE_RATIO: f64 = 1.47;
+        const NOISE_HIGH_PASS_HZ: f64 = 1000.0;
+        const NOISE_HIGH_PASS_Q: f64 = 0.7;
+        // the tone decays faster than the noise
+        const TONE_DECAY_RATIO: f64 = 0.5;
+
+        pub struct Props {
+            pub trigger: Sbool,
+            /// frequency of the tonal part of the drum
+            pub frequency_hz: Sf64,
+            pub decay_seconds: Sf64,
+            /// 0 is entirely noise and 1 is entirely tone
+            pub tone_01: Sf64,
+        }
+
+        struct Signal {
+            props: Props,
+            rng: XorShiftRng,
+            noise_amplitude: Decay,
+            tone_amplitude: Decay,
+            noise_filter: Biquad,
+            phases_01: [f64; 2],
+        }
+
+        impl Signal {
+            fn new(props: Props) -> Self {
+                Self {
+                    props,
+                    rng: new_rng(),
+                    noise_amplitude: Decay::new(),
+                    tone_amplitude: Decay::new(),
+                    noise_filter: Default::default(),
+                    phases_01: [0.0; 2],
+                }
+            }
+        }
+
+        impl SignalTrait<f64> for Signal {
+            fn sample(&mut self, ctx: &SignalCtx) -> f64 {
+                let trigger = self.props.trigger.sample(ctx);
+                if trigger {
+                    self.phases_01 = [0.0; 2];
+                }
+                let decay_seconds = self.props.decay_seconds.sample(ctx);
+                let noise_amplitude =
+                    self.noise_amplitude
+                        .sample(trigger, decay_seconds, ctx.sample_rate);
+                let tone_amplitude = self.tone_amplitude.sample(
+                    trigger,
+                    decay_seconds * TONE_DECAY_RATIO,
+                    ctx.sample_rate,
+                );
+                let frequency_hz = self.props.frequency_hz.sample(ctx);
+                let mut tone = 0.0;
+                for (phase_01, ratio) in self.phases_01.iter_mut().zip([1.0, OVERTONE_RATIO]) {
+                    *phase_01 = (*phase_01 + ((frequency_hz * ratio) / ctx.sample_rate as f64))
+                        .rem_euclid(1.0);
+                    tone += (*phase_01 * 2.0 * PI).sin() / 2.0;
+                }
+                self.noise_filter.set_high_pass(
+                    NOISE_HIGH_PASS_HZ,
+                    NOISE_HIGH_PASS_Q,
+                    ctx.sample_rate,
+                );
+                let noise = self.noise_filter.apply(white_noise(&mut self.rng));
+                let tone_01 = self.props.tone_01.sample(ctx).clamp(0.0, 1.0);
+                (tone * tone_amplitude * tone_01) + (noise * noise_amplitude * (1.0 - tone_01))
+            }
+        }
+
+        pub fn create(props: Props) -> Sf64 {
+            Sf64::new(Signal::new(props))
+        }
+    }
+
+    pub mod hi_hat {
+        use super::*;
+        use crate::signal::*;
+
+        // Ratios between the frequencies of the square waves of the TR-808's cymbal circuit
+        const OSCILLATOR_RATIOS: [f64; 6] = [1.0, 1.4827, 1.8003, 2.5460, 2.6303, 3.8967];
+        const BAND_PASS_Q: f64 = 1.0;
+        // the high pass filter removes the low end left by the band pass filter
+        const HIGH_PASS_RATIO: f64 = 0.7;
+        const HIGH_PASS_Q: f64 = 0.7;
+
+        pub struct Props {
+            pub trigger: Sbool,
+            /// frequency of the lowest of the square oscillators
+            pub frequency_hz: Sf64,
+            pub decay_seconds: Sf64,
+            /// centre frequency of the band pass filter
+            pub tone_hz: Sf64,
+        }
+
+        struct Signal {
+            props: Props,
+            amplitude: Decay,
+            band_pass: Biquad,
+            high_pass: Biquad,
+            phases_01: [f64; 6],
+        }
+
+        impl Signal {
+            fn new(props: Props) -> Self {
+                Self {
+                    props,
+                    amplitude: Decay::new(),
+                    band_pass: Default::default(),
+                    high_pass: Default::default(),
+                    phases_01: [0.0; 6],
+                }
+            }
+        }
+
+        impl SignalTrait<f64> for Signal {
+            fn sample(&mut self, ctx: &SignalCtx) -> f64 {
+                let trigger = self.props.trigger.sample(ctx);
+                let amplitude = self.amplitude.sample(
+                    trigger,
+                    self.props.decay_seconds.sample(ctx),
+                    ctx.sample_rate,
+                );
+                let frequency_hz = self.props.frequency_hz.sample(ctx);
+                let mut cluster = 0.0;
+                for (phase_01, ratio) in self.phases_01.iter_mut().zip(OSCILLATOR_RATIOS) {
+                    *phase_01 = (*phase_01 + ((frequency_hz * ratio) / ctx.sample_rate as f64))
+                        .rem_euclid(1.0);
+                    cluster += if *phase_01 < 0.5 { -1.0 } else { 1.0 };
+                }
+                cluster /= OSCILLATOR_RATIOS.len() as f64;
+                let tone_hz = self.props.tone_hz.sample(ctx);
+                self.band_pass
+                    .set_band_pass(tone_hz, BAND_PASS_Q, ctx.sample_rate);
+                self.high_pass.set_high_pass(
+                    tone_hz * HIGH_PASS_RATIO,
+                    HIGH_PASS_Q,
+                    ctx.sample_rate,
+                );
+                self.high_pass.apply(self.band_pass.apply(cluster)) * amplitude
+            }
+        }
+
+        pub fn create(props: Props) -> Sf64 {
+            Sf64::new(Signal::new(props))
+        }
+    }
+
+    pub mod clap {
+        use super::*;
+        use crate::signal::*;
+
+        // A clap is made of several short bursts of noise in quick succession followed by a
+        // longer tail.
+        const NUM_BURSTS: u64 = 3;
+        const BURST_INTERVAL_SECONDS: f64 = 0.01;
+        const BURST_DECAY_SECONDS: f64 = 0.02;
+        const BAND_PASS_Q: f64 = 2.0;
+
+        pub struct Props {
+            pub trigger: Sbool,
+            /// decay time of the tail following the bursts
+            pub decay_seconds: Sf64,
+            /// centre frequency of the band pass filter
+            pub tone_hz: Sf64,
+        }
+
+        struct Signal {
+            props: Props,
+            rng: XorShiftRng,
+            band_pass: Biquad,
+            samples_since_trigger: Option<u64>,
+        }
+
+        impl Signal {
+            fn new(props: Props) -> Self {
+                Self {
+                    props,
+                    rng: new_rng(),
+                    band_pass: Default::default(),
+                    samples_since_trigger: None,
+                }
+            }
+        }
+
+        impl SignalTrait<f64> for Signal {
+            fn sample(&mut self, ctx: &SignalCtx) -> f64 {
+                if self.props.trigger.sample(ctx) {
+                    self.samples_since_trigger = Some(0);
+                }
+                let decay_seconds = self.props.decay_seconds.sample(ctx).max(MIN_DECAY_SECONDS);
+                let tone_hz = self.props.tone_hz.sample(ctx);
+                let amplitude =
+                    if let Some(samples_since_trigger) = self.samples_since_trigger.as_mut() {
+                        let sample_rate = ctx.sample_rate as f64;
+                        let burst_interval_samples =
+                            ((BURST_INTERVAL_SECONDS * sample_rate) as u64).max(1);
+                        let (elapsed_samples, decay_seconds) =
+                            if *samples_since_trigger < NUM_BURSTS * burst_interval_samples {
+                                (
+                                    *samples_since_trigger % burst_interval_samples,
+                                    BURST_DECAY_SECONDS,
+                                )
+                            } else {
+                                (
+                                    *samples_since_trigger - (NUM_BURSTS * burst_interval_samples),
+                                    decay_seconds,
+                                )
+                            };
+                        *samples_since_trigger += 1;
+                        0.001_f64.powf(elapsed_samples as f64 / (decay_seconds * sample_rate))
+                    } else {
+                        0.0
+                    };
+                self.band_pass
+                    .set_band_pass(tone_hz, BAND_PASS_Q, ctx.sample_rate);
+                self.band_pass.apply(white_noise(&mut self.rng)) * amplitude
+            }
+        }
+
+        pub fn create(props: Props) -> Sf64 {
+            Sf64::new(Signal::new(props))
+        }
+    }
+
+    #[cfg(test)]
+    mod test {
+        use super::*;
+
+        #[test]
+        fn decay_clamps_short_decay_times() {
+            for decay_seconds in [0.0, -1.0, f64::NAN] {
+                let mut decay = Decay::new();
+                assert_eq!(decay.sample(true, decay_seconds, 44100), 1.0);
+                for _ in 0..10 {
+                    let value = decay.sample(false, decay_seconds, 44100);
+                    assert!((0.0..1.0).contains(&value), "{decay_seconds}: {value}");
+                }
+            }
+        }
+    }
+}
+
+pub mod granular {
+    use super::random_uniform::rng;
+    use crate::{sample::Sample, signal::*};
+    use rand::Rng;
+    use rand_xorshift::XorShiftRng;
+    use std::f64::consts::PI;
+
+    // New grains are not started while this many grains are playing
+    const MAX_GRAINS: usize = 64;
+
+    pub struct Props {
+        pub sample: Sample,
+        pub grain_size_seconds: Sf64,
+        /// number of grains started per second
+        pub density_hz: Sf64,
+        /// position in the sample where grains start, where 0 is the start and 1 is the end
+        pub position_01: Sf64,
+        /// maximum random offset added to the position of each grain, as a proportion of the
+        /// length of the sample
+        pub position_jitter_01: Sf64,
+        /// playback rate of each grain, where 1 plays the sample at its original pitch
+        pub pitch: Sf64,
+        /// shape of the window applied to each grain, where 0 is rectangular and 1 is a hann window
+        pub window_shape_01: Sf64,
+        pub seed: Option<u64>,
+    }
+
+    struct Grain {
+        position: f64,
+        step: f64,
+        age_samples: f64,
+        length_samples: f64,
+        window_shape_01: f64,
+    }
+
+    // Tukey window which is rectangular when `shape_01` is 0 and a hann window when it's 1
+    fn window(x_01: f64, shape_01: f64) -> f64 {
+        if shape_01 <= 0.0 {
+            return 1.0;
+        }
+        let edge = shape_01 / 2.0;
+        if x_01 < edge {
+            0.5 * (1.0 - ((PI * x_01) / edge).cos())
+        } else if x_01 > 1.0 - edge {
+            0.5 * (1.0 - ((PI * (1.0 - x_01)) / edge).cos())
+        } else {
+            1.0
+        }
+    }
+
+    struct Signal {
+        props: Props,
+        rng: XorShiftRng,
+        grains: Vec<Grain>,
+        phase_01: f64,
+    }
+
+    impl Signal {
+        fn new(props: Props) -> Self {
+            Self {
+                rng: rng(props.seed),
+                props,
+                grains: Vec::with_capacity(MAX_GRAINS),
+                // start a grain on the first sample
+                phase_01: 1.0,
+            }
+        }
+    }
+
+    impl SignalTrait<f64> for Signal {
+        fn sample(&mut self, ctx: &SignalCtx) -> f64 {
+            let sample_rate = ctx.sample_rate as f64;
+            let grain_size_seconds = self.props.grain_size_seconds.sample(ctx);
+            let density_hz = self.props.density_hz.sample(ctx);
+            let position_01 = self.props.position_01.sample(ctx);
+            let position_jitter_01 = self.props.position_jitter_01.sample(ctx);
+            let pitch = self.props.pitch.sample(ctx);
+            let window_shape_01 = self.props.window_shape_01.sample(ctx).clamp(0.0, 1.0);
+            self.phase_01 += density_hz / sample_rate;
+            if self.phase_01 >= 1.0 {
+                self.phase_01 = self.phase_01.rem_euclid(1.0);
+                let length_samples = grain_size_seconds * sample_rate;
+                if self.grains.len() < MAX_GRAINS && length_samples >= 1.0 {
+                    let jitter = position_jitter_01 * ((self.rng.gen::<f64>() * 2.0) - 1.0);
+                    let start_01 = (position_01 + jitter).clamp(0.0, 1.0);
+                    self.grains.push(Grain {
+                        position: start_01 * self.props.sample.len() as f64,
+                        step: pitch * (self.props.sample.sample_rate_hz() as f64 / sample_rate),
+                        age_samples: 0.0,
+                        length_samples,
+                        window_shape_01,
+                    });
+                }
+            }
+            let sample = &self.props.sample;
+            let mut output = 0.0;
+            for grain in self.grains.iter_mut() {
+                let x_01 = grain.age_samples / grain.length_samples;
+                output += sample.interpolate(grain.position) * window(x_01, grain.window_shape_01);
+                grain.position += grain.step;
+                grain.age_samples += 1.0;
+            }
+            self.grains
+                .retain(|grain| grain.age_samples < grain.length_samples);
+            output
+        }
+    }
+
+    pub fn create(props: Props) -> Sf64 {
+        Sf64::new(Signal::new(props))
     }
 }
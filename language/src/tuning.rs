@@ -0,0 +1,429 @@
+//! Mapping from keys (numbered like MIDI notes) to frequencies, including support for the Scala
+//! scale (.scl) and keyboard mapping (.kbm) file formats.
+use std::{error, fmt, fs, io, path::Path};
+
+const CENTS_PER_OCTAVE: f64 = 1200.0;
+
+#[derive(Debug)]
+pub enum TuningError {
+    Io(io::Error),
+    Parse { line: usize, message: String },
+}
+
+impl fmt::Display for TuningError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "io error: {}", error),
+            Self::Parse { line, message } => write!(f, "line {}: {}", line, message),
+        }
+    }
+}
+
+impl error::Error for TuningError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::Io(error) => Some(error),
+            Self::Parse { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for TuningError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+// Non-comment lines of a Scala file, with surrounding whitespace removed, along with their
+// 1-based line numbers
+fn scala_lines(s: &str) -> impl Iterator<Item = (usize, &str)> {
+    s.lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.starts_with('!'))
+}
+
+fn parse_error<T>(line: usize, message: impl Into<String>) -> Result<T, TuningError> {
+    Err(TuningError::Parse {
+        line,
+        message: message.into(),
+    })
+}
+
+// Parses a pitch from a .scl file, which is in cents if it contains a '.' and otherwise is a
+// ratio such as "3/2" or "2"
+fn parse_scl_pitch_cents(line: usize, s: &str) -> Result<f64, TuningError> {
+    let value = s.split_whitespace().next().unwrap_or("");
+    if value.contains('.') {
+        return value
+            .parse::<f64>()
+            .or_else(|_| parse_error(line, format!("invalid cents value: {}", value)));
+    }
+    let (numerator, denominator) = value.split_once('/').unwrap_or((value, "1"));
+    match (numerator.parse::<u64>(), denominator.parse::<u64>()) {
+        (Ok(numerator), Ok(denominator)) if numerator > 0 && denominator > 0 => {
+            Ok(CENTS_PER_OCTAVE * (numerator as f64 / denominator as f64).log2())
+        }
+        _ => parse_error(line, format!("invalid ratio: {}", value)),
+    }
+}
+
+/// Describes which keys play which degrees of a scale, and which key plays a reference frequency
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyboardMap {
+    /// key which plays degree 0 of the scale
+    pub middle_key: i32,
+    pub reference_key: i32,
+    pub reference_frequency_hz: f64,
+    /// scale degree played by the key `mapping.len()` keys above the middle key
+    pub octave_degree: i32,
+    /// scale degree played by each key in a repeating pattern starting at the middle key, where
+    /// `None` indicates that a key isn't mapped. If empty, consecutive keys play consecutive
+    /// scale degrees.
+    pub mapping: Vec<Option<i32>>,
+}
+
+impl Default for KeyboardMap {
+    /// Middle C (key 60) plays degree 0 and A4 (key 69) is 440Hz
+    fn default() -> Self {
+        Self {
+            middle_key: 60,
+            reference_key: 69,
+            reference_frequency_hz: 440.0,
+            octave_degree: 0,
+            mapping: Vec::new(),
+        }
+    }
+}
+
+impl KeyboardMap {
+    /// Parses the contents of a Scala keyboard mapping (.kbm) file
+    pub fn from_kbm(s: &str) -> Result<Self, TuningError> {
+        let end_line = s.lines().count();
+        let mut lines = scala_lines(s).filter(|(_, line)| !line.is_empty());
+        let mut next_value = |name: &str| match lines.next() {
+            Some((line, s)) => Ok((line, s.split_whitespace().next().unwrap_or(""))),
+            None => parse_error(end_line, format!("missing {}", name)),
+        };
+        fn parse<T: std::str::FromStr>((line, s): (usize, &str)) -> Result<T, TuningError> {
+            s.parse::<T>()
+                .or_else(|_| parse_error(line, format!("invalid value: {}", s)))
+        }
+        let map_size = parse::<usize>(next_value("map size")?)?;
+        // the range of keys to retune isn't needed as every key is retuned
+        let _first_key = parse::<i32>(next_value("first note")?)?;
+        let _last_key = parse::<i32>(next_value("last note")?)?;
+        let middle_key = parse::<i32>(next_value("middle note")?)?;
+        let reference_key = parse::<i32>(next_value("reference note")?)?;
+        let reference_frequency_hz = parse::<f64>(next_value("reference frequency")?)?;
+        let octave_degree = parse::<i32>(next_value("octave degree")?)?;
+        let mut mapping = Vec::with_capacity(map_size);
+        for _ in 0..map_size {
+            // missing entries at the end of the mapping are unmapped
+            match lines.next() {
+                Some((_, s)) if s.starts_with('x') => mapping.push(None),
+                Some((line, s)) => mapping.push(Some(parse::<i32>((
+                    line,
+                    s.split_whitespace().next().unwrap_or(""),
+                ))?)),
+                None => mapping.push(None),
+            }
+        }
+        Ok(Self {
+            middle_key,
+            reference_key,
+            reference_frequency_hz,
+            octave_degree,
+            mapping,
+        })
+    }
+
+    pub fn from_kbm_file<P: AsRef<Path>>(path: P) -> Result<Self, TuningError> {
+        Self::from_kbm(&fs::read_to_string(path)?)
+    }
+
+    /// The scale degree played by a key or `None` if the key isn't mapped
+    pub fn degree(&self, key: i32) -> Option<i32> {
+        let offset = key - self.middle_key;
+        if self.mapping.is_empty() {
+            return Some(offset);
+        }
+        let size = self.mapping.len() as i32;
+        let octave = offset.div_euclid(size);
+        self.mapping[offset.rem_euclid(size) as usize]
+            .map(|degree| (octave * self.octave_degree) + degree)
+    }
+}
+
+/// A scale which repeats at some period (usually an octave) along with a keyboard map which
+/// determines how keys are mapped onto the scale
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tuning {
+    description: String,
+    /// pitch of each degree of the scale above degree 0 in cents, where the final entry is the
+    /// period at which the scale repeats
+    cents: Vec<f64>,
+    keyboard_map: KeyboardMap,
+}
+
+impl Default for Tuning {
+    /// 12-tone equal temperament where A4 (key 69) is 440Hz
+    fn default() -> Self {
+        Self::equal_temperament(12)
+    }
+}
+
+impl Tuning {
+    /// Scale from the pitches of each degree above degree 0 in cents, where the final entry is
+    /// the period at which the scale repeats. Uses the default keyboard map.
+    pub fn from_cents(description: impl Into<String>, cents: Vec<f64>) -> Self {
+        assert!(!cents.is_empty(), "tuning must contain at least one pitch");
+        Self {
+            description: description.into(),
+            cents,
+            keyboard_map: KeyboardMap::default(),
+        }
+    }
+
+    /// Scale from frequency ratios of each degree above degree 0, where the final entry is the
+    /// period at which the scale repeats (usually 2)
+    pub fn from_ratios(description: impl Into<String>, ratios: &[f64]) -> Self {
+        Self::from_cents(
+            description,
+            ratios
+                .iter()
+                .map(|ratio| CENTS_PER_OCTAVE * ratio.log2())
+                .collect(),
+        )
+    }
+
+    /// Octave divided into `divisions` equal steps
+    pub fn equal_temperament(divisions: usize) -> Self {
+        let divisions = divisions.max(1);
+        Self::from_cents(
+            format!("{}-tone equal temperament", divisions),
+            (1..=divisions)
+                .map(|i| (i as f64 * CENTS_PER_OCTAVE) / divisions as f64)
+                .collect(),
+        )
+    }
+
+    /// 12-tone 5-limit just intonation
+    pub fn just_intonation() -> Self {
+        Self::from_ratios(
+            "5-limit just intonation",
+            &[
+                16.0 / 15.0,
+                9.0 / 8.0,
+                6.0 / 5.0,
+                5.0 / 4.0,
+                4.0 / 3.0,
+                45.0 / 32.0,
+                3.0 / 2.0,
+                8.0 / 5.0,
+                5.0 / 3.0,
+                9.0 / 5.0,
+                15.0 / 8.0,
+                2.0,
+            ],
+        )
+    }
+
+    /// Parses the contents of a Scala scale (.scl) file. Uses the default keyboard map.
+    pub fn from_scl(s: &str) -> Result<Self, TuningError> {
+        let end_line = s.lines().count();
+        let mut lines = scala_lines(s);
+        let description = match lines.next() {
+            Some((_, line)) => line.to_string(),
+            None => return parse_error(end_line, "missing description"),
+        };
+        let (line, count) = match lines.find(|(_, line)| !line.is_empty()) {
+            Some((line, s)) => (line, s.split_whitespace().next().unwrap_or("")),
+            None => return parse_error(end_line, "missing number of notes"),
+        };
+        let count = match count.parse::<usize>() {
+            Ok(0) => return parse_error(line, "scale must contain at least one note"),
+            Ok(count) => count,
+            Err(_) => return parse_error(line, format!("invalid number of notes: {}", count)),
+        };
+        let mut cents = Vec::with_capacity(count);
+        for (line, s) in lines.filter(|(_, line)| !line.is_empty()).take(count) {
+            cents.push(parse_scl_pitch_cents(line, s)?);
+        }
+        if cents.len() < count {
+            return parse_error(
+                end_line,
+                format!("expected {} notes but found {}", count, cents.len()),
+            );
+        }
+        Ok(Self::from_cents(description, cents))
+    }
+
+    pub fn from_scl_file<P: AsRef<Path>>(path: P) -> Result<Self, TuningError> {
+        Self::from_scl(&fs::read_to_string(path)?)
+    }
+
+    pub fn with_keyboard_map(self, keyboard_map: KeyboardMap) -> Self {
+        Self {
+            keyboard_map,
+            ..self
+        }
+    }
+
+    /// Change which key plays which frequency, keeping the rest of the keyboard map
+    pub fn with_reference(self, reference_key: i32, reference_frequency_hz: f64) -> Self {
+        Self {
+            keyboard_map: KeyboardMap {
+                reference_key,
+                reference_frequency_hz,
+                ..self.keyboard_map
+            },
+            ..self
+        }
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    pub fn keyboard_map(&self) -> &KeyboardMap {
+        &self.keyboard_map
+    }
+
+    /// Number of degrees in each period of the scale
+    pub fn len(&self) -> usize {
+        self.cents.len()
+    }
+
+    /// Always false as every tuning contains at least one pitch
+    pub fn is_empty(&self) -> bool {
+        self.cents.is_empty()
+    }
+
+    /// Pitch of a scale degree relative to degree 0 in cents
+    pub fn degree_cents(&self, degree: i32) -> f64 {
+        let len = self.cents.len() as i32;
+        let period_cents = self.cents[self.cents.len() - 1];
+        let period = degree.div_euclid(len);
+        let index = degree.rem_euclid(len);
+        let cents_in_period = if index == 0 {
+            0.0
+        } else {
+            self.cents[index as usize - 1]
+        };
+        (period as f64 * period_cents) + cents_in_period
+    }
+
+    /// Frequency played by a key (numbered like MIDI notes) or `None` if the key isn't mapped
+    pub fn frequency(&self, key: i32) -> Option<f64> {
+        let map = &self.keyboard_map;
+        let degree = map.degree(key)?;
+        // an unmapped reference key is treated as though keys were mapped to consecutive degrees
+        let reference_degree = map
+            .degree(map.reference_key)
+            .unwrap_or(map.reference_key - map.middle_key);
+        let cents = self.degree_cents(degree) - self.degree_cents(reference_degree);
+        Some(map.reference_frequency_hz * (cents / CENTS_PER_OCTAVE).exp2())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn assert_parse_error<T: fmt::Debug>(result: Result<T, TuningError>, expected_line: usize) {
+        match result {
+            Err(TuningError::Parse { line, .. }) => assert_eq!(line, expected_line),
+            other => panic!("expected parse error on line {expected_line}: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn scl() {
+        let scl = "! example.scl
+!
+Example scale
+ 4
+  ! indented comment
+ 100.0 cents
+ 3/2
+ 700.5
+ 2
+";
+        let tuning = Tuning::from_scl(scl).unwrap();
+        assert_eq!(tuning.description(), "Example scale");
+        assert_eq!(tuning.len(), 4);
+        assert_eq!(tuning.degree_cents(1), 100.0);
+        assert!((tuning.degree_cents(2) - 701.955).abs() < 1e-3);
+        assert_eq!(tuning.degree_cents(3), 700.5);
+        assert_eq!(tuning.degree_cents(4), 1200.0);
+        assert_eq!(tuning.degree_cents(-1), 700.5 - 1200.0);
+    }
+
+    #[test]
+    fn malformed_scl() {
+        assert_parse_error(Tuning::from_scl(""), 0);
+        assert_parse_error(Tuning::from_scl("description\n"), 1);
+        assert_parse_error(Tuning::from_scl("description\nfour\n"), 2);
+        assert_parse_error(Tuning::from_scl("description\n0\n"), 2);
+        assert_parse_error(Tuning::from_scl("description\n2\n100.0\n"), 3);
+        assert_parse_error(Tuning::from_scl("description\n2\n100.0\n3/0\n"), 4);
+        assert_parse_error(Tuning::from_scl("description\n1\n1.2.3\n"), 3);
+    }
+
+    #[test]
+    fn kbm() {
+        let kbm = "! example.kbm
+5
+0
+127
+60
+69
+432.0
+3
+! degree played by each key
+0
+x
+1
+  ! indented comment
+2
+";
+        let keyboard_map = KeyboardMap::from_kbm(kbm).unwrap();
+        assert_eq!(
+            keyboard_map,
+            KeyboardMap {
+                middle_key: 60,
+                reference_key: 69,
+                reference_frequency_hz: 432.0,
+                octave_degree: 3,
+                // the final entry is missing so it's unmapped
+                mapping: vec![Some(0), None, Some(1), Some(2), None],
+            }
+        );
+        assert_eq!(keyboard_map.degree(60), Some(0));
+        assert_eq!(keyboard_map.degree(61), None);
+        assert_eq!(keyboard_map.degree(62), Some(1));
+        assert_eq!(keyboard_map.degree(65), Some(3));
+        assert_eq!(keyboard_map.degree(59), None);
+        assert_eq!(keyboard_map.degree(58), Some(-1));
+    }
+
+    #[test]
+    fn malformed_kbm() {
+        assert_parse_error(KeyboardMap::from_kbm("12\n0\n127\n"), 3);
+        assert_parse_error(KeyboardMap::from_kbm("12\n0\n127\nsixty\n"), 4);
+        assert_parse_error(
+            KeyboardMap::from_kbm("2\n0\n127\n60\n69\n440.0\n2\n0\ny\n"),
+            9,
+        );
+    }
+
+    #[test]
+    fn frequency() {
+        let tuning = Tuning::default();
+        assert_eq!(tuning.frequency(69), Some(440.0));
+        assert_eq!(tuning.frequency(57), Some(220.0));
+        let tuning = tuning.with_reference(60, 256.0);
+        assert_eq!(tuning.frequency(72), Some(512.0));
+    }
+}
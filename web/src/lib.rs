@@ -1,14 +1,24 @@
 use chargrid_web::{Context, Size};
-use synth_app::{self, args, music};
+use synth_app::{self, args, keyboard_layout, music};
 use wasm_bindgen::prelude::*;
 
 fn run_synth() {
     let context = Context::new(Size::new(100, 60), "content");
     let args = args::Args {
         volume_scale: 0.1,
-        start_note: music::note(music::NoteName::C, 2),
+        start_note: music::note(music::NoteName::C, 3),
         downsample: 2,
         render_scale: 1.0,
+        drum_kit: None,
+        arp: false,
+        scale: None,
+        scl: None,
+        kbm: None,
+        keyboard_layout: keyboard_layout::KeyboardLayout::qwerty(),
+        keyboard_layout_file: None,
+        osc_port: None,
+        preset: None,
+        preset_dir: "presets".into(),
     };
     context.run(synth_app::app(args).unwrap());
 }